@@ -10,16 +10,20 @@ use bpfd_api::{
     util::directories::*,
     v1::{
         list_response, load_request, load_request_common, loader_client::LoaderClient,
-        BytecodeImage, ListRequest, LoadRequest, LoadRequestCommon, TcAttachInfo,
-        TracepointAttachInfo, UnloadRequest, XdpAttachInfo,
+        BytecodeImage, CgroupAttachInfo, FollowRequest, FollowResponse, GetVersionRequest,
+        ClearProgramRequest, KprobeAttachInfo, ListRequest, LoadRequest, LoadRequestCommon,
+        MapDeleteRequest, MapKeysRequest, MapLookupRequest, MapUpdateRequest, SetProgramRequest,
+        TcAttachInfo, TracepointAttachInfo, UnloadRequest, UprobeAttachInfo, UsdtAttachInfo,
+        XdpAttachInfo,
     },
-    ImagePullPolicy, ProgramType, TcProceedOn, XdpProceedOn,
+    ImagePullPolicy, ProgramType, TcProceedOn, XdpProceedOn, PROTOCOL_VERSION,
 };
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use comfy_table::Table;
 use hex::FromHex;
 use itertools::Itertools;
 use log::{debug, info};
+use serde_json::json;
 use tokio::net::UnixStream;
 use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity, Uri};
 use tower::service_fn;
@@ -27,10 +31,22 @@ use tower::service_fn;
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Cli {
+    /// Output format for command results and errors.
+    #[clap(long, value_enum, global = true, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+
     #[clap(subcommand)]
     command: Commands,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable tables, the default.
+    Table,
+    /// A single JSON value per command, suitable for scripting.
+    Json,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Load a BPF program from a local .o file.
@@ -41,6 +57,69 @@ enum Commands {
     Unload { id: String },
     /// List all BPF programs loaded via bpfd.
     List,
+    /// Stream events a loaded program pushes into its perf event array map,
+    /// one event per line, until interrupted with Ctrl-C. Ring buffer maps
+    /// are not yet supported.
+    Follow { id: String },
+    /// Look up a single key in one of a loaded program's pinned maps.
+    MapLookup {
+        /// UUID of the program that owns the map.
+        id: String,
+        /// Name of the pinned map, e.g. "my_map".
+        map_name: String,
+        /// Hex-encoded key to look up.
+        key: String,
+    },
+    /// Insert or update a single key/value pair in one of a loaded
+    /// program's pinned maps.
+    MapUpdate {
+        /// UUID of the program that owns the map.
+        id: String,
+        /// Name of the pinned map, e.g. "my_map".
+        map_name: String,
+        /// Hex-encoded key to insert or update.
+        key: String,
+        /// Hex-encoded value to write.
+        value: String,
+    },
+    /// Delete a single key from one of a loaded program's pinned maps.
+    MapDelete {
+        /// UUID of the program that owns the map.
+        id: String,
+        /// Name of the pinned map, e.g. "my_map".
+        map_name: String,
+        /// Hex-encoded key to delete.
+        key: String,
+    },
+    /// List every key currently stored in one of a loaded program's pinned
+    /// maps.
+    MapKeys {
+        /// UUID of the program that owns the map.
+        id: String,
+        /// Name of the pinned map, e.g. "my_map".
+        map_name: String,
+    },
+    /// Register a loaded program into another program's BPF_MAP_TYPE_PROG_ARRAY
+    /// tail-call slot.
+    SetProgram {
+        /// UUID of the program that owns the PROG_ARRAY map.
+        id: String,
+        /// Name of the pinned PROG_ARRAY map.
+        map_name: String,
+        /// Index (tail call key) within the PROG_ARRAY to set.
+        index: u32,
+        /// UUID of the already-loaded program to tail-call into.
+        target_id: String,
+    },
+    /// Clear a previously set BPF_MAP_TYPE_PROG_ARRAY tail-call slot.
+    ClearProgram {
+        /// UUID of the program that owns the PROG_ARRAY map.
+        id: String,
+        /// Name of the pinned PROG_ARRAY map.
+        map_name: String,
+        /// Index (tail call key) within the PROG_ARRAY to clear.
+        index: u32,
+    },
 }
 
 #[derive(Args)]
@@ -143,6 +222,95 @@ enum LoadCommands {
         #[clap(short, long)]
         tracepoint: String,
     },
+    Uprobe {
+        /// Required: Location of the library or binary to attach the uprobe to.
+        /// E.g /usr/lib/libc.so.6
+        #[clap(short, long)]
+        target: String,
+        /// Optional: Symbol name to attach the uprobe to. Mutually exclusive with --offset.
+        #[clap(short, long, conflicts_with = "offset")]
+        fn_name: Option<String>,
+        /// Optional: Address offset within the target to attach the uprobe to,
+        /// as a hex string. Mutually exclusive with --fn-name.
+        #[clap(short, long, value_parser=parse_hex_u64)]
+        offset: Option<u64>,
+        /// Optional: Only attach the uprobe in the context of the provided process ID.
+        #[clap(short, long)]
+        pid: Option<i32>,
+        /// Optional: Attach as a return probe.
+        #[clap(short, long)]
+        retprobe: bool,
+    },
+    Kprobe {
+        /// Required: Kernel symbol name to attach the kprobe to.
+        #[clap(short, long)]
+        fn_name: String,
+        /// Optional: Address offset within the symbol to attach the kprobe to,
+        /// as a hex string. Not allowed for retprobes.
+        #[clap(short, long, value_parser=parse_hex_u64, default_value = "0")]
+        offset: u64,
+        /// Optional: Attach as a return probe.
+        #[clap(short, long)]
+        retprobe: bool,
+    },
+    Usdt {
+        /// Required: Location of the library or binary containing the USDT probe.
+        /// E.g /usr/lib/libc.so.6
+        #[clap(short, long)]
+        target: String,
+        /// Required: USDT provider name. E.g "libc".
+        #[clap(short, long)]
+        provider: String,
+        /// Required: USDT probe name. E.g "memory_mallopt_arena_max".
+        #[clap(long)]
+        probe: String,
+        /// Optional: Only attach the usdt probe in the context of the provided process ID.
+        #[clap(long)]
+        pid: Option<i32>,
+    },
+    CgroupSkb {
+        /// Required: Path to the cgroup to attach to. E.g /sys/fs/cgroup/unified/...
+        #[clap(short, long)]
+        cgroup: String,
+        /// Required: Direction to apply program. "ingress" or "egress"
+        #[clap(short, long)]
+        direction: String,
+    },
+    CgroupSock {
+        /// Required: Path to the cgroup to attach to. E.g /sys/fs/cgroup/unified/...
+        #[clap(short, long)]
+        cgroup: String,
+        /// Required: The cgroup_sock attach point.
+        /// Possible values: [post_bind4, post_bind6, bind4, bind6, connect4, connect6, sock_create, sock_release]
+        #[clap(short, long)]
+        attach_type: String,
+    },
+    CgroupSockopt {
+        /// Required: Path to the cgroup to attach to. E.g /sys/fs/cgroup/unified/...
+        #[clap(short, long)]
+        cgroup: String,
+        /// Required: The cgroup_sockopt attach point.
+        /// Possible values: [getsockopt, setsockopt]
+        #[clap(short, long)]
+        attach_type: String,
+    },
+}
+
+const CGROUP_SOCK_ATTACH_TYPES: &[&str] = &[
+    "post_bind4",
+    "post_bind6",
+    "bind4",
+    "bind6",
+    "connect4",
+    "connect6",
+    "sock_create",
+    "sock_release",
+];
+
+const CGROUP_SOCKOPT_ATTACH_TYPES: &[&str] = &["getsockopt", "setsockopt"];
+
+fn parse_hex_u64(offset: &str) -> Result<u64, std::num::ParseIntError> {
+    u64::from_str_radix(offset.trim_start_matches("0x"), 16)
 }
 
 #[derive(Clone, Debug)]
@@ -198,9 +366,12 @@ async fn main() -> anyhow::Result<()> {
     {
         Ok(channel) => {
             info!("Using UNIX socket as transport");
-            match execute_request(&cli.command, channel).await {
-                Ok(_) => return Ok(()),
-                Err(e) => eprintln!("Error = {e:?}"),
+            match negotiate_version(channel.clone()).await {
+                Ok(supported) => match execute_request(&cli.command, channel, cli.format, &supported).await {
+                    Ok(_) => return Ok(()),
+                    Err(e) => print_error(cli.format, &e),
+                },
+                Err(e) => print_error(cli.format, &e),
             }
         }
         Err(e) => debug!("Error getting UNIX socket channel. Err: {}", e),
@@ -236,14 +407,58 @@ async fn main() -> anyhow::Result<()> {
         .await?;
 
     info!("Using TLS over TCP socket as transport");
-    if let Err(e) = execute_request(&cli.command, channel).await {
-        eprintln!("Error = {e:?}")
+    let supported = negotiate_version(channel.clone()).await?;
+    if let Err(e) = execute_request(&cli.command, channel, cli.format, &supported).await {
+        print_error(cli.format, &e)
     }
 
     Ok(())
 }
 
-async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result<()> {
+// Checked once per connection, right after the channel is established and
+// before any load/list/unload call runs, so a mismatched bpfctl/bpfd pair
+// fails with a clear message instead of a confusing decode error partway
+// through a real request. Also returns the program types the daemon
+// reports supporting, so the CLI can reject e.g. a Usdt load against an
+// older daemon up front rather than letting it fail server-side.
+async fn negotiate_version(channel: Channel) -> anyhow::Result<Vec<ProgramType>> {
+    let mut client = LoaderClient::new(channel);
+    let response = client
+        .get_version(tonic::Request::new(GetVersionRequest {}))
+        .await?
+        .into_inner();
+
+    if response.version != PROTOCOL_VERSION {
+        bail!(
+            "bpfctl protocol v{PROTOCOL_VERSION} cannot talk to bpfd protocol v{}; upgrade one side",
+            response.version
+        );
+    }
+
+    Ok(response
+        .supported_program_types
+        .into_iter()
+        .filter_map(|t| ProgramType::try_from(t).ok())
+        .collect())
+}
+
+// Errors are normally reported as `Error = {e:?}` on stderr for a human to
+// read. In JSON format that mix of free text and data would force every
+// caller to tell the two apart itself, so report the same error as a
+// `{"error": "..."}` object on stderr instead.
+fn print_error(format: OutputFormat, e: &anyhow::Error) {
+    match format {
+        OutputFormat::Table => eprintln!("Error = {e:?}"),
+        OutputFormat::Json => eprintln!("{}", json!({ "error": e.to_string() })),
+    }
+}
+
+async fn execute_request(
+    command: &Commands,
+    channel: Channel,
+    format: OutputFormat,
+    supported: &[ProgramType],
+) -> anyhow::Result<()> {
     let mut client = LoaderClient::new(channel);
     match command {
         Commands::LoadFromFile(l) => {
@@ -251,7 +466,21 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
                 LoadCommands::Xdp { .. } => ProgramType::Xdp,
                 LoadCommands::Tc { .. } => ProgramType::Tc,
                 LoadCommands::Tracepoint { .. } => ProgramType::Tracepoint,
+                LoadCommands::Uprobe { .. } => ProgramType::Uprobe,
+                LoadCommands::Kprobe { .. } => ProgramType::Kprobe,
+                LoadCommands::Usdt { .. } => ProgramType::Usdt,
+                LoadCommands::CgroupSkb { .. } => ProgramType::CgroupSkb,
+                LoadCommands::CgroupSock { .. } => ProgramType::CgroupSock,
+                LoadCommands::CgroupSockopt { .. } => ProgramType::CgroupSockopt,
             };
+
+            if !supported.contains(&prog_type) {
+                bail!(
+                    "bpfd does not support {} programs; upgrade the daemon",
+                    prog_type.to_string()
+                );
+            }
+
             let attach_type = match &l.command {
                 LoadCommands::Xdp {
                     iface,
@@ -296,6 +525,93 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
                         tracepoint: tracepoint.to_string(),
                     }),
                 ),
+                LoadCommands::Uprobe {
+                    target,
+                    fn_name,
+                    offset,
+                    pid,
+                    retprobe,
+                } => {
+                    if fn_name.is_none() && offset.is_none() {
+                        bail!("uprobe requires either --fn-name or --offset");
+                    }
+                    Some(load_request::AttachInfo::UprobeAttachInfo(
+                        UprobeAttachInfo {
+                            target: target.to_string(),
+                            fn_name: fn_name.clone(),
+                            offset: offset.unwrap_or(0),
+                            pid: *pid,
+                            retprobe: *retprobe,
+                        },
+                    ))
+                }
+                LoadCommands::Kprobe {
+                    fn_name,
+                    offset,
+                    retprobe,
+                } => {
+                    if *retprobe && *offset != 0 {
+                        bail!("offset is not allowed for kretprobes");
+                    }
+                    Some(load_request::AttachInfo::KprobeAttachInfo(
+                        KprobeAttachInfo {
+                            fn_name: fn_name.to_string(),
+                            offset: *offset,
+                            retprobe: *retprobe,
+                        },
+                    ))
+                }
+                LoadCommands::Usdt {
+                    target,
+                    provider,
+                    probe,
+                    pid,
+                } => Some(load_request::AttachInfo::UsdtAttachInfo(UsdtAttachInfo {
+                    target: target.to_string(),
+                    provider: provider.to_string(),
+                    probe: probe.to_string(),
+                    pid: *pid,
+                })),
+                LoadCommands::CgroupSkb { cgroup, direction } => {
+                    match direction.as_str() {
+                        "ingress" | "egress" => (),
+                        other => bail!("{} is not a valid direction", other),
+                    };
+                    Some(load_request::AttachInfo::CgroupAttachInfo(
+                        CgroupAttachInfo {
+                            cgroup: cgroup.to_string(),
+                            attach_type: direction.to_string(),
+                        },
+                    ))
+                }
+                LoadCommands::CgroupSock {
+                    cgroup,
+                    attach_type,
+                } => {
+                    if !CGROUP_SOCK_ATTACH_TYPES.contains(&attach_type.as_str()) {
+                        bail!("{attach_type} is not a valid cgroup_sock attach type");
+                    }
+                    Some(load_request::AttachInfo::CgroupAttachInfo(
+                        CgroupAttachInfo {
+                            cgroup: cgroup.to_string(),
+                            attach_type: attach_type.to_string(),
+                        },
+                    ))
+                }
+                LoadCommands::CgroupSockopt {
+                    cgroup,
+                    attach_type,
+                } => {
+                    if !CGROUP_SOCKOPT_ATTACH_TYPES.contains(&attach_type.as_str()) {
+                        bail!("{attach_type} is not a valid cgroup_sockopt attach type");
+                    }
+                    Some(load_request::AttachInfo::CgroupAttachInfo(
+                        CgroupAttachInfo {
+                            cgroup: cgroup.to_string(),
+                            attach_type: attach_type.to_string(),
+                        },
+                    ))
+                }
             };
 
             let mut global_data: HashMap<String, Vec<u8>> = HashMap::new();
@@ -318,14 +634,28 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
                 attach_info: attach_type,
             });
             let response = client.load(request).await?.into_inner();
-            println!("{}", response.id);
+            print_load_result(format, &response.id);
         }
         Commands::LoadFromImage(l) => {
             let prog_type = match l.command {
                 LoadCommands::Xdp { .. } => ProgramType::Xdp,
                 LoadCommands::Tc { .. } => ProgramType::Tc,
                 LoadCommands::Tracepoint { .. } => ProgramType::Tracepoint,
+                LoadCommands::Uprobe { .. } => ProgramType::Uprobe,
+                LoadCommands::Kprobe { .. } => ProgramType::Kprobe,
+                LoadCommands::Usdt { .. } => ProgramType::Usdt,
+                LoadCommands::CgroupSkb { .. } => ProgramType::CgroupSkb,
+                LoadCommands::CgroupSock { .. } => ProgramType::CgroupSock,
+                LoadCommands::CgroupSockopt { .. } => ProgramType::CgroupSockopt,
             };
+
+            if !supported.contains(&prog_type) {
+                bail!(
+                    "bpfd does not support {} programs; upgrade the daemon",
+                    prog_type.to_string()
+                );
+            }
+
             let attach_type = match &l.command {
                 LoadCommands::Xdp {
                     iface,
@@ -370,6 +700,93 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
                         tracepoint: tracepoint.to_string(),
                     }),
                 ),
+                LoadCommands::Uprobe {
+                    target,
+                    fn_name,
+                    offset,
+                    pid,
+                    retprobe,
+                } => {
+                    if fn_name.is_none() && offset.is_none() {
+                        bail!("uprobe requires either --fn-name or --offset");
+                    }
+                    Some(load_request::AttachInfo::UprobeAttachInfo(
+                        UprobeAttachInfo {
+                            target: target.to_string(),
+                            fn_name: fn_name.clone(),
+                            offset: offset.unwrap_or(0),
+                            pid: *pid,
+                            retprobe: *retprobe,
+                        },
+                    ))
+                }
+                LoadCommands::Kprobe {
+                    fn_name,
+                    offset,
+                    retprobe,
+                } => {
+                    if *retprobe && *offset != 0 {
+                        bail!("offset is not allowed for kretprobes");
+                    }
+                    Some(load_request::AttachInfo::KprobeAttachInfo(
+                        KprobeAttachInfo {
+                            fn_name: fn_name.to_string(),
+                            offset: *offset,
+                            retprobe: *retprobe,
+                        },
+                    ))
+                }
+                LoadCommands::Usdt {
+                    target,
+                    provider,
+                    probe,
+                    pid,
+                } => Some(load_request::AttachInfo::UsdtAttachInfo(UsdtAttachInfo {
+                    target: target.to_string(),
+                    provider: provider.to_string(),
+                    probe: probe.to_string(),
+                    pid: *pid,
+                })),
+                LoadCommands::CgroupSkb { cgroup, direction } => {
+                    match direction.as_str() {
+                        "ingress" | "egress" => (),
+                        other => bail!("{} is not a valid direction", other),
+                    };
+                    Some(load_request::AttachInfo::CgroupAttachInfo(
+                        CgroupAttachInfo {
+                            cgroup: cgroup.to_string(),
+                            attach_type: direction.to_string(),
+                        },
+                    ))
+                }
+                LoadCommands::CgroupSock {
+                    cgroup,
+                    attach_type,
+                } => {
+                    if !CGROUP_SOCK_ATTACH_TYPES.contains(&attach_type.as_str()) {
+                        bail!("{attach_type} is not a valid cgroup_sock attach type");
+                    }
+                    Some(load_request::AttachInfo::CgroupAttachInfo(
+                        CgroupAttachInfo {
+                            cgroup: cgroup.to_string(),
+                            attach_type: attach_type.to_string(),
+                        },
+                    ))
+                }
+                LoadCommands::CgroupSockopt {
+                    cgroup,
+                    attach_type,
+                } => {
+                    if !CGROUP_SOCKOPT_ATTACH_TYPES.contains(&attach_type.as_str()) {
+                        bail!("{attach_type} is not a valid cgroup_sockopt attach type");
+                    }
+                    Some(load_request::AttachInfo::CgroupAttachInfo(
+                        CgroupAttachInfo {
+                            cgroup: cgroup.to_string(),
+                            attach_type: attach_type.to_string(),
+                        },
+                    ))
+                }
             };
 
             let image_pull_policy: ImagePullPolicy = l
@@ -421,7 +838,7 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
             });
 
             let response = client.load(request).await?.into_inner();
-            println!("{}", response.id);
+            print_load_result(format, &response.id);
         }
 
         Commands::Unload { id } => {
@@ -434,11 +851,12 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
             let mut table = Table::new();
             table.load_preset(comfy_table::presets::NOTHING);
             table.set_header(vec!["UUID", "Type", "Name", "Location", "Metadata"]);
+            let mut records = Vec::new();
             for r in response.results {
                 let prog_type: ProgramType = r.program_type.try_into()?;
-                match prog_type {
+                let (type_name, metadata) = match prog_type {
                     ProgramType::Xdp => {
-                        if let Some(list_response::list_result::AttachInfo::XdpAttachInfo(
+                        let Some(list_response::list_result::AttachInfo::XdpAttachInfo(
                             XdpAttachInfo {
                                 priority,
                                 iface,
@@ -446,22 +864,20 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
                                 proceed_on,
                             },
                         )) = r.attach_info
-                        {
-                            let proc_on = match XdpProceedOn::from_int32s(proceed_on) {
-                                Ok(p) => p,
-                                Err(e) => bail!("error parsing proceed_on {e}"),
-                            };
-                            table.add_row(vec![
-                            r.id.to_string(),
-                            "xdp".to_string(),
-                            r.section_name.unwrap(),
-                            r.location.unwrap().to_string(),
-                            format!(r#"{{ "priority": {priority}, "iface": "{iface}", "position": {position}, "proceed_on": {proc_on} }}"#)
-                        ]);
-                        }
+                        else {
+                            continue;
+                        };
+                        let proc_on = match XdpProceedOn::from_int32s(proceed_on) {
+                            Ok(p) => p,
+                            Err(e) => bail!("error parsing proceed_on {e}"),
+                        };
+                        (
+                            "xdp",
+                            json!({ "priority": priority, "iface": iface, "position": position, "proceed_on": proc_on.to_string() }),
+                        )
                     }
                     ProgramType::Tc => {
-                        if let Some(list_response::list_result::AttachInfo::TcAttachInfo(
+                        let Some(list_response::list_result::AttachInfo::TcAttachInfo(
                             TcAttachInfo {
                                 priority,
                                 iface,
@@ -470,36 +886,214 @@ async fn execute_request(command: &Commands, channel: Channel) -> anyhow::Result
                                 proceed_on: _,
                             },
                         )) = r.attach_info
-                        {
-                            table.add_row(vec![
-                                r.id.to_string(),
-                                "tc".to_string(),
-                                r.section_name.unwrap(),
-                                r.location.unwrap().to_string(),
-                                format!(r#"{{ "priority": {priority}, "iface": "{iface}", "position": {position}, direction: {direction} }}"#)
-                            ]);
-                        }
+                        else {
+                            continue;
+                        };
+                        (
+                            "tc",
+                            json!({ "priority": priority, "iface": iface, "position": position, "direction": direction }),
+                        )
                     }
                     ProgramType::Tracepoint => {
-                        if let Some(list_response::list_result::AttachInfo::TracepointAttachInfo(
+                        let Some(list_response::list_result::AttachInfo::TracepointAttachInfo(
                             TracepointAttachInfo { tracepoint },
                         )) = r.attach_info
-                        {
-                            table.add_row(vec![
-                                r.id.to_string(),
-                                "tracepoint".to_string(),
-                                r.section_name.unwrap(),
-                                r.location.unwrap().to_string(),
-                                format!(r#"{{ "tracepoint": {tracepoint} }}"#),
-                            ]);
-                        }
+                        else {
+                            continue;
+                        };
+                        ("tracepoint", json!({ "tracepoint": tracepoint }))
+                    }
+                    ProgramType::CgroupSkb => {
+                        let Some(list_response::list_result::AttachInfo::CgroupAttachInfo(
+                            CgroupAttachInfo {
+                                cgroup,
+                                attach_type,
+                            },
+                        )) = r.attach_info
+                        else {
+                            continue;
+                        };
+                        (
+                            "cgroup_skb",
+                            json!({ "cgroup": cgroup, "direction": attach_type }),
+                        )
+                    }
+                    ProgramType::CgroupSock => {
+                        let Some(list_response::list_result::AttachInfo::CgroupAttachInfo(
+                            CgroupAttachInfo {
+                                cgroup,
+                                attach_type,
+                            },
+                        )) = r.attach_info
+                        else {
+                            continue;
+                        };
+                        (
+                            "cgroup_sock",
+                            json!({ "cgroup": cgroup, "attach_type": attach_type }),
+                        )
+                    }
+                    ProgramType::CgroupSockopt => {
+                        let Some(list_response::list_result::AttachInfo::CgroupAttachInfo(
+                            CgroupAttachInfo {
+                                cgroup,
+                                attach_type,
+                            },
+                        )) = r.attach_info
+                        else {
+                            continue;
+                        };
+                        (
+                            "cgroup_sockopt",
+                            json!({ "cgroup": cgroup, "attach_type": attach_type }),
+                        )
                     }
                     // skip unknown program types
-                    _ => {}
+                    _ => continue,
+                };
+
+                let section_name = r.section_name.unwrap();
+                let location = r.location.unwrap().to_string();
+
+                table.add_row(vec![
+                    r.id.to_string(),
+                    type_name.to_string(),
+                    section_name.clone(),
+                    location.clone(),
+                    metadata.to_string(),
+                ]);
+                records.push(json!({
+                    "id": r.id,
+                    "type": type_name,
+                    "section_name": section_name,
+                    "location": location,
+                    "attach_info": metadata,
+                }));
+            }
+
+            match format {
+                OutputFormat::Table => println!("{table}"),
+                OutputFormat::Json => println!("{}", serde_json::to_string(&records)?),
+            }
+        }
+        Commands::Follow { id } => {
+            let request = tonic::Request::new(FollowRequest { id: id.to_string() });
+            let mut stream = client.follow(request).await?.into_inner();
+
+            loop {
+                tokio::select! {
+                    msg = stream.message() => {
+                        match msg? {
+                            Some(event) => print_follow_event(format, &event),
+                            // Server closed the stream, e.g. the program was unloaded.
+                            None => break,
+                        }
+                    }
+                    _ = tokio::signal::ctrl_c() => break,
                 }
             }
-            println!("{table}");
+        }
+        Commands::MapLookup { id, map_name, key } => {
+            let key = Vec::<u8>::from_hex(&key).context("key is not valid hex")?;
+            let request = tonic::Request::new(MapLookupRequest {
+                id: id.to_string(),
+                map_name: map_name.clone(),
+                key,
+            });
+            let response = client.map_lookup(request).await?.into_inner();
+            print_map_value(format, &response.value);
+        }
+        Commands::MapUpdate {
+            id,
+            map_name,
+            key,
+            value,
+        } => {
+            let key = Vec::<u8>::from_hex(&key).context("key is not valid hex")?;
+            let value = Vec::<u8>::from_hex(&value).context("value is not valid hex")?;
+            let request = tonic::Request::new(MapUpdateRequest {
+                id: id.to_string(),
+                map_name: map_name.clone(),
+                key,
+                value,
+            });
+            let _response = client.map_update(request).await?.into_inner();
+        }
+        Commands::MapDelete { id, map_name, key } => {
+            let key = Vec::<u8>::from_hex(&key).context("key is not valid hex")?;
+            let request = tonic::Request::new(MapDeleteRequest {
+                id: id.to_string(),
+                map_name: map_name.clone(),
+                key,
+            });
+            let _response = client.map_delete(request).await?.into_inner();
+        }
+        Commands::MapKeys { id, map_name } => {
+            let request = tonic::Request::new(MapKeysRequest {
+                id: id.to_string(),
+                map_name: map_name.clone(),
+            });
+            let response = client.map_keys(request).await?.into_inner();
+            print_map_keys(format, &response.keys);
+        }
+        Commands::SetProgram {
+            id,
+            map_name,
+            index,
+            target_id,
+        } => {
+            let request = tonic::Request::new(SetProgramRequest {
+                id: id.to_string(),
+                map_name: map_name.clone(),
+                index,
+                target_id: target_id.to_string(),
+            });
+            let _response = client.set_program(request).await?.into_inner();
+        }
+        Commands::ClearProgram {
+            id,
+            map_name,
+            index,
+        } => {
+            let request = tonic::Request::new(ClearProgramRequest {
+                id: id.to_string(),
+                map_name: map_name.clone(),
+                index,
+            });
+            let _response = client.clear_program(request).await?.into_inner();
         }
     }
     Ok(())
 }
+
+fn print_load_result(format: OutputFormat, id: &str) {
+    match format {
+        OutputFormat::Table => println!("{id}"),
+        OutputFormat::Json => println!("{}", json!({ "id": id })),
+    }
+}
+
+fn print_follow_event(format: OutputFormat, event: &FollowResponse) {
+    let data = hex::encode(&event.data);
+    match format {
+        OutputFormat::Table => println!("{data}"),
+        OutputFormat::Json => println!("{}", json!({ "data": data })),
+    }
+}
+
+fn print_map_value(format: OutputFormat, value: &[u8]) {
+    let value = hex::encode(value);
+    match format {
+        OutputFormat::Table => println!("{value}"),
+        OutputFormat::Json => println!("{}", json!({ "value": value })),
+    }
+}
+
+fn print_map_keys(format: OutputFormat, keys: &[Vec<u8>]) {
+    let keys: Vec<String> = keys.iter().map(hex::encode).collect();
+    match format {
+        OutputFormat::Table => keys.iter().for_each(|k| println!("{k}")),
+        OutputFormat::Json => println!("{}", json!({ "keys": keys })),
+    }
+}
+
@@ -12,6 +12,13 @@ use thiserror::Error;
 use url::ParseError as urlParseError;
 use v1::{Direction, ProceedOn, ProgramType};
 
+/// RPC protocol version this build of bpfd/bpfctl speaks. Bumped whenever a
+/// breaking change is made to the `Loader` service (new required fields,
+/// removed RPCs, ...), so a client and daemon built from different
+/// revisions fail the version handshake loudly instead of hitting confusing
+/// decode errors partway through a request.
+pub const PROTOCOL_VERSION: u32 = 3;
+
 #[derive(Error, Debug)]
 pub enum ParseError {
     #[error("{program} is not a valid program type")]
@@ -39,6 +46,12 @@ impl ToString for ProgramType {
             ProgramType::Xdp => "xdp".to_owned(),
             ProgramType::Tc => "tc".to_owned(),
             ProgramType::Tracepoint => "tracepoint".to_owned(),
+            ProgramType::Uprobe => "uprobe".to_owned(),
+            ProgramType::Kprobe => "kprobe".to_owned(),
+            ProgramType::Usdt => "usdt".to_owned(),
+            ProgramType::CgroupSkb => "cgroup_skb".to_owned(),
+            ProgramType::CgroupSock => "cgroup_sock".to_owned(),
+            ProgramType::CgroupSockopt => "cgroup_sockopt".to_owned(),
         }
     }
 }
@@ -51,6 +64,12 @@ impl TryFrom<String> for ProgramType {
             "xdp" => ProgramType::Xdp,
             "tc" => ProgramType::Tc,
             "tracepoint" => ProgramType::Tracepoint,
+            "uprobe" => ProgramType::Uprobe,
+            "kprobe" => ProgramType::Kprobe,
+            "usdt" => ProgramType::Usdt,
+            "cgroup_skb" => ProgramType::CgroupSkb,
+            "cgroup_sock" => ProgramType::CgroupSock,
+            "cgroup_sockopt" => ProgramType::CgroupSockopt,
             program => {
                 return Err(ParseError::InvalidProgramType {
                     program: program.to_string(),
@@ -121,6 +140,12 @@ impl TryFrom<i32> for ProgramType {
             0 => ProgramType::Xdp,
             1 => ProgramType::Tc,
             2 => ProgramType::Tracepoint,
+            3 => ProgramType::Uprobe,
+            4 => ProgramType::Kprobe,
+            5 => ProgramType::Usdt,
+            6 => ProgramType::CgroupSkb,
+            7 => ProgramType::CgroupSock,
+            8 => ProgramType::CgroupSockopt,
             other => {
                 return Err(ParseError::InvalidProgramType {
                     program: other.to_string(),
@@ -4,16 +4,19 @@
 use std::{
     collections::HashMap,
     convert::TryInto,
-    fs::{create_dir_all, read_dir, remove_dir_all},
+    fs::{create_dir_all, read_dir, remove_dir_all, remove_file},
+    os::fd::AsRawFd,
     path::{Path, PathBuf},
 };
 
 use anyhow::anyhow;
 use aya::{
+    maps::{perf::AsyncPerfEventArray, MapData},
     programs::{
         kprobe::KProbeLink, links::FdLink, loaded_programs, trace_point::TracePointLink,
-        uprobe::UProbeLink, KProbe, TracePoint, UProbe,
+        uprobe::UProbeLink, usdt::UsdtLink, KProbe, TracePoint, UProbe, Usdt,
     },
+    util::online_cpus,
     BpfLoader,
 };
 use bpfman_api::{
@@ -22,8 +25,12 @@ use bpfman_api::{
     ProbeType::{self, *},
     ProgramType,
 };
+use bytes::BytesMut;
 use log::{debug, info};
-use tokio::{select, sync::mpsc::Receiver};
+use tokio::{
+    select,
+    sync::mpsc::{self, Receiver},
+};
 
 use crate::{
     command::{
@@ -46,9 +53,19 @@ pub(crate) struct BpfManager {
     dispatchers: DispatcherMap,
     programs: ProgramMap,
     maps: HashMap<u32, BpfMap>,
+    prog_arrays: HashMap<(u32, String), ProgArraySlots>,
     commands: Receiver<Command>,
 }
 
+// Tracks which program id occupies each populated index of a
+// `BPF_MAP_TYPE_PROG_ARRAY`, keyed by the (owner_id, map_name) of the
+// program that owns the array, so `remove_program` can find and clear
+// every slot a program participates in as a tail-call target.
+#[derive(Default)]
+pub(crate) struct ProgArraySlots {
+    slots: HashMap<u32, u32>,
+}
+
 pub(crate) struct ProgramMap {
     programs: HashMap<u32, Program>,
 }
@@ -199,6 +216,7 @@ impl BpfManager {
             dispatchers: DispatcherMap::new(),
             programs: ProgramMap::new(),
             maps: HashMap::new(),
+            prog_arrays: HashMap::new(),
             commands,
         }
     }
@@ -215,7 +233,7 @@ impl BpfManager {
 
         // re-build programs from database
         for tree_name in ROOT_DB.tree_names() {
-            let name = &bytes_to_string(&tree_name);
+            let name = &bytes_to_string(&tree_name)?;
             let tree = ROOT_DB
                 .open_tree(name)
                 .expect("unable to open database tree");
@@ -309,6 +327,15 @@ impl BpfManager {
         if let Some(map_owner_id) = map_owner_id {
             let map_pin_path = self.is_map_owner_id_valid(map_owner_id)?;
             program.get_data_mut().set_map_pin_path(&map_pin_path)?;
+        } else if let Some(external_map_pin_path) = program.get_data().get_external_map_pin_path()? {
+            // The caller is adopting maps pinned by another tool (iproute2,
+            // a libbpf app, ...) instead of asking bpfman to own fresh
+            // ones. Point the loader at that directory directly; Aya
+            // reuses whatever is already pinned there and only creates
+            // what's missing.
+            program
+                .get_data_mut()
+                .set_map_pin_path(&external_map_pin_path)?;
         }
 
         program
@@ -321,9 +348,10 @@ impl BpfManager {
 
                 self.add_multi_attach_program(&mut program)
             }
-            Program::Tracepoint(_) | Program::Kprobe(_) | Program::Uprobe(_) => {
-                self.add_single_attach_program(&mut program)
-            }
+            Program::Tracepoint(_)
+            | Program::Kprobe(_)
+            | Program::Uprobe(_)
+            | Program::Usdt(_) => self.add_single_attach_program(&mut program),
             Program::Unsupported(_) => panic!("Cannot add unsupported program"),
         };
 
@@ -346,6 +374,12 @@ impl BpfManager {
                 // by the kernel.
                 program.get_data_mut().swap_tree(id)?;
 
+                // Pin the backing image, if any, so it survives cache
+                // eviction for as long as this program is loaded.
+                if let Some(image_content_key) = program.get_data().get_image_content_key()? {
+                    IMAGE_MANAGER.lock().unwrap().pin_image(&image_content_key);
+                }
+
                 // Only add program to bpfManager if we've completed all mutations and it's successfully loaded.
                 self.programs.insert(id, program.to_owned());
 
@@ -472,9 +506,24 @@ impl BpfManager {
             .allow_unsupported_maps()
             .load(p.get_data().program_bytes())?;
 
-        let raw_program = loader
-            .program_mut(name)
-            .ok_or(BpfmanError::BpfFunctionNameNotValid(name.to_owned()))?;
+        // Kprobe/uprobe objects containing many probe functions (e.g. a
+        // tracing suite shipping dozens of syscall probes) can be loaded
+        // once and have every function matching a user-supplied pattern
+        // attached, instead of requiring one load per function.
+        let fn_name_pattern = p
+            .get_data()
+            .get_fn_name_pattern()?
+            .filter(|_| matches!(p, Program::Kprobe(_) | Program::Uprobe(_)));
+
+        let raw_program = if fn_name_pattern.is_none() {
+            Some(
+                loader
+                    .program_mut(name)
+                    .ok_or(BpfmanError::BpfFunctionNameNotValid(name.to_owned()))?,
+            )
+        } else {
+            None
+        };
 
         let res = match p {
             Program::Tracepoint(ref mut program) => {
@@ -488,6 +537,8 @@ impl BpfManager {
                 let category = parts[0].to_owned();
                 let name = parts[1].to_owned();
 
+                let raw_program =
+                    raw_program.expect("tracepoint programs are not batch-attached");
                 let tracepoint: &mut TracePoint = raw_program.try_into()?;
 
                 tracepoint.load()?;
@@ -526,38 +577,100 @@ impl BpfManager {
                     )));
                 }
 
-                let kprobe: &mut KProbe = raw_program.try_into()?;
-                kprobe.load()?;
+                if let Some(pattern) = fn_name_pattern.as_deref() {
+                    let fn_names: Vec<String> = loader
+                        .programs()
+                        .map(|(name, _)| name.to_owned())
+                        .filter(|name| matches_probe_pattern(name, pattern))
+                        .collect();
 
-                // verify that the program loaded was the same type as the
-                // user requested
-                let loaded_probe_type = ProbeType::from(kprobe.kind());
-                if requested_probe_type != loaded_probe_type {
-                    return Err(BpfmanError::Error(format!(
-                        "expected {requested_probe_type}, loaded program is {loaded_probe_type}"
-                    )));
-                }
+                    if fn_names.is_empty() {
+                        return Err(BpfmanError::BpfFunctionNameNotValid(pattern.to_owned()));
+                    }
 
-                program.get_data_mut().set_kernel_info(&kprobe.info()?)?;
+                    for fn_name in &fn_names {
+                        let kprobe: &mut KProbe = loader
+                            .program_mut(fn_name)
+                            .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(fn_name.clone()))?
+                            .try_into()?;
+                        kprobe.load()?;
+
+                        let loaded_probe_type = ProbeType::from(kprobe.kind());
+                        if requested_probe_type != loaded_probe_type {
+                            return Err(BpfmanError::Error(format!(
+                                "expected {requested_probe_type}, loaded program is {loaded_probe_type}"
+                            )));
+                        }
+                    }
 
-                let id = program.data.get_id()?;
+                    let first: &mut KProbe = loader
+                        .program_mut(&fn_names[0])
+                        .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(fn_names[0].clone()))?
+                        .try_into()?;
+                    program.get_data_mut().set_kernel_info(&first.info()?)?;
+                    let id = program.data.get_id()?;
+
+                    for fn_name in &fn_names {
+                        let kprobe: &mut KProbe = loader
+                            .program_mut(fn_name)
+                            .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(fn_name.clone()))?
+                            .try_into()?;
+
+                        let link_id = kprobe.attach(fn_name, program.get_offset()?)?;
+                        let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+                        let fd_link: FdLink = owned_link
+                            .try_into()
+                            .expect("unable to get owned kprobe attach link");
 
-                let link_id = kprobe.attach(program.get_fn_name()?, program.get_offset()?)?;
+                        fd_link
+                            .pin(format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}_link"))
+                            .map_err(BpfmanError::UnableToPinLink)?;
 
-                let owned_link: KProbeLink = kprobe.take_link(link_id)?;
-                let fd_link: FdLink = owned_link
-                    .try_into()
-                    .expect("unable to get owned kprobe attach link");
+                        kprobe
+                            .pin(format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}"))
+                            .map_err(BpfmanError::UnableToPinProgram)?;
+                    }
 
-                fd_link
-                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
-                    .map_err(BpfmanError::UnableToPinLink)?;
+                    program.get_data_mut().set_batch_attachments(fn_names)?;
 
-                kprobe
-                    .pin(format!("{RTDIR_FS}/prog_{id}"))
-                    .map_err(BpfmanError::UnableToPinProgram)?;
+                    Ok(id)
+                } else {
+                    let raw_program =
+                        raw_program.expect("kprobe was already loaded in batch branch");
+                    let kprobe: &mut KProbe = raw_program.try_into()?;
+                    kprobe.load()?;
+
+                    // verify that the program loaded was the same type as the
+                    // user requested
+                    let loaded_probe_type = ProbeType::from(kprobe.kind());
+                    if requested_probe_type != loaded_probe_type {
+                        return Err(BpfmanError::Error(format!(
+                            "expected {requested_probe_type}, loaded program is {loaded_probe_type}"
+                        )));
+                    }
 
-                Ok(id)
+                    program.get_data_mut().set_kernel_info(&kprobe.info()?)?;
+
+                    let id = program.data.get_id()?;
+
+                    let link_id =
+                        kprobe.attach(program.get_fn_name()?, program.get_offset()?)?;
+
+                    let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+                    let fd_link: FdLink = owned_link
+                        .try_into()
+                        .expect("unable to get owned kprobe attach link");
+
+                    fd_link
+                        .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                        .map_err(BpfmanError::UnableToPinLink)?;
+
+                    kprobe
+                        .pin(format!("{RTDIR_FS}/prog_{id}"))
+                        .map_err(BpfmanError::UnableToPinProgram)?;
+
+                    Ok(id)
+                }
             }
             Program::Uprobe(ref mut program) => {
                 let requested_probe_type = match program.get_retprobe()? {
@@ -565,76 +678,217 @@ impl BpfManager {
                     false => Uprobe,
                 };
 
-                let uprobe: &mut UProbe = raw_program.try_into()?;
-                uprobe.load()?;
+                if let Some(pattern) = fn_name_pattern.as_deref() {
+                    if program.get_container_pid()?.is_some() {
+                        return Err(BpfmanError::Error(
+                            "batch attach is not supported for uprobes in a different container"
+                                .to_string(),
+                        ));
+                    }
 
-                // verify that the program loaded was the same type as the
-                // user requested
-                let loaded_probe_type = ProbeType::from(uprobe.kind());
-                if requested_probe_type != loaded_probe_type {
-                    return Err(BpfmanError::Error(format!(
-                        "expected {requested_probe_type}, loaded program is {loaded_probe_type}"
-                    )));
-                }
+                    let fn_names: Vec<String> = loader
+                        .programs()
+                        .map(|(name, _)| name.to_owned())
+                        .filter(|name| matches_probe_pattern(name, pattern))
+                        .collect();
 
-                program.get_data_mut().set_kernel_info(&uprobe.info()?)?;
+                    if fn_names.is_empty() {
+                        return Err(BpfmanError::BpfFunctionNameNotValid(pattern.to_owned()));
+                    }
 
-                let id = program.data.get_id()?;
+                    for fn_name in &fn_names {
+                        let uprobe: &mut UProbe = loader
+                            .program_mut(fn_name)
+                            .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(fn_name.clone()))?
+                            .try_into()?;
+                        uprobe.load()?;
+
+                        let loaded_probe_type = ProbeType::from(uprobe.kind());
+                        if requested_probe_type != loaded_probe_type {
+                            return Err(BpfmanError::Error(format!(
+                                "expected {requested_probe_type}, loaded program is {loaded_probe_type}"
+                            )));
+                        }
+                    }
 
-                let program_pin_path = format!("{RTDIR_FS}/prog_{id}");
-                let fn_name = program.get_fn_name()?;
+                    let first: &mut UProbe = loader
+                        .program_mut(&fn_names[0])
+                        .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(fn_names[0].clone()))?
+                        .try_into()?;
+                    program.get_data_mut().set_kernel_info(&first.info()?)?;
+                    let id = program.data.get_id()?;
 
-                uprobe
-                    .pin(program_pin_path.clone())
-                    .map_err(BpfmanError::UnableToPinProgram)?;
+                    for fn_name in &fn_names {
+                        let uprobe: &mut UProbe = loader
+                            .program_mut(fn_name)
+                            .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(fn_name.clone()))?
+                            .try_into()?;
 
-                match program.get_container_pid()? {
-                    None => {
-                        // Attach uprobe in same container as the bpfman process
                         let link_id = uprobe.attach(
-                            fn_name.as_deref(),
+                            Some(fn_name.as_str()),
                             program.get_offset()?,
                             program.get_target()?,
                             None,
                         )?;
-
                         let owned_link: UProbeLink = uprobe.take_link(link_id)?;
                         let fd_link: FdLink = owned_link
                             .try_into()
                             .expect("unable to get owned uprobe attach link");
 
+                        fd_link
+                            .pin(format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}_link"))
+                            .map_err(BpfmanError::UnableToPinLink)?;
+
+                        uprobe
+                            .pin(format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}"))
+                            .map_err(BpfmanError::UnableToPinProgram)?;
+                    }
+
+                    program.get_data_mut().set_batch_attachments(fn_names)?;
+
+                    Ok(id)
+                } else {
+                    let raw_program =
+                        raw_program.expect("uprobe was already loaded in batch branch");
+                    let uprobe: &mut UProbe = raw_program.try_into()?;
+                    uprobe.load()?;
+
+                    // verify that the program loaded was the same type as the
+                    // user requested
+                    let loaded_probe_type = ProbeType::from(uprobe.kind());
+                    if requested_probe_type != loaded_probe_type {
+                        return Err(BpfmanError::Error(format!(
+                            "expected {requested_probe_type}, loaded program is {loaded_probe_type}"
+                        )));
+                    }
+
+                    program.get_data_mut().set_kernel_info(&uprobe.info()?)?;
+
+                    let id = program.data.get_id()?;
+
+                    let program_pin_path = format!("{RTDIR_FS}/prog_{id}");
+                    let fn_name = program.get_fn_name()?;
+
+                    uprobe
+                        .pin(program_pin_path.clone())
+                        .map_err(BpfmanError::UnableToPinProgram)?;
+
+                    match program.get_container_pid()? {
+                        None => {
+                            // Attach uprobe in same container as the bpfman process
+                            let link_id = uprobe.attach(
+                                fn_name.as_deref(),
+                                program.get_offset()?,
+                                program.get_target()?,
+                                None,
+                            )?;
+
+                            let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+                            let fd_link: FdLink = owned_link
+                                .try_into()
+                                .expect("unable to get owned uprobe attach link");
+
+                            fd_link
+                                .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                                .map_err(BpfmanError::UnableToPinLink)?;
+                        }
+                        Some(p) => {
+                            // Attach uprobe in different container from the bpfman process
+                            let offset = program.get_offset()?.to_string();
+                            let container_pid = p.to_string();
+                            let mut prog_args = vec![
+                                "uprobe".to_string(),
+                                "--program-pin-path".to_string(),
+                                program_pin_path,
+                                "--offset".to_string(),
+                                offset,
+                                "--target".to_string(),
+                                program.get_target()?.to_string(),
+                                "--container-pid".to_string(),
+                                container_pid,
+                            ];
+
+                            if let Some(fn_name) = &program.get_fn_name()? {
+                                prog_args.extend(["--fn-name".to_string(), fn_name.to_string()])
+                            }
+
+                            if program.get_retprobe()? {
+                                prog_args.push("--retprobe".to_string());
+                            }
+
+                            if let Some(pid) = program.get_pid()? {
+                                prog_args.extend(["--pid".to_string(), pid.to_string()])
+                            }
+
+                            let status = std::process::Command::new("./target/debug/bpfman-ns")
+                                .args(prog_args)
+                                .status()
+                                .expect("bpfman-ns call failed to return status");
+
+                            debug!("bpfman-ns status: {:?}", status);
+
+                            if !status.success() {
+                                return Err(BpfmanError::ContainerAttachError {
+                                    program_type: "uprobe".to_string(),
+                                    container_pid: program.get_container_pid()?.unwrap(),
+                                });
+                            }
+                        }
+                    };
+
+                    Ok(id)
+                }
+            }
+            Program::Usdt(ref mut program) => {
+                let raw_program = raw_program.expect("usdt programs are not batch-attached");
+                let usdt: &mut Usdt = raw_program.try_into()?;
+                usdt.load()?;
+
+                program.get_data_mut().set_kernel_info(&usdt.info()?)?;
+
+                let id = program.data.get_id()?;
+
+                let program_pin_path = format!("{RTDIR_FS}/prog_{id}");
+                let provider = program.get_provider()?;
+                let probe = program.get_probe()?;
+                let target = program.get_target()?;
+
+                usdt.pin(program_pin_path.clone())
+                    .map_err(BpfmanError::UnableToPinProgram)?;
+
+                match program.get_container_pid()? {
+                    None => {
+                        // Attach in the same container as the bpfman process.
+                        let link_id = usdt.attach(&provider, &probe, target, None)?;
+
+                        let owned_link: UsdtLink = usdt.take_link(link_id)?;
+                        let fd_link: FdLink = owned_link
+                            .try_into()
+                            .expect("unable to get owned usdt attach link");
+
                         fd_link
                             .pin(format!("{RTDIR_FS}/prog_{}_link", id))
                             .map_err(BpfmanError::UnableToPinLink)?;
                     }
                     Some(p) => {
-                        // Attach uprobe in different container from the bpfman process
-                        let offset = program.get_offset()?.to_string();
+                        // Attach the USDT marker in a different container from the
+                        // bpfman process via the same out-of-band mechanism used for
+                        // cross-container uprobes.
                         let container_pid = p.to_string();
-                        let mut prog_args = vec![
-                            "uprobe".to_string(),
+                        let prog_args = vec![
+                            "usdt".to_string(),
                             "--program-pin-path".to_string(),
                             program_pin_path,
-                            "--offset".to_string(),
-                            offset,
+                            "--provider".to_string(),
+                            provider.clone(),
+                            "--probe".to_string(),
+                            probe.clone(),
                             "--target".to_string(),
-                            program.get_target()?.to_string(),
+                            target.to_string(),
                             "--container-pid".to_string(),
                             container_pid,
                         ];
 
-                        if let Some(fn_name) = &program.get_fn_name()? {
-                            prog_args.extend(["--fn-name".to_string(), fn_name.to_string()])
-                        }
-
-                        if program.get_retprobe()? {
-                            prog_args.push("--retprobe".to_string());
-                        }
-
-                        if let Some(pid) = program.get_pid()? {
-                            prog_args.extend(["--pid".to_string(), pid.to_string()])
-                        }
-
                         let status = std::process::Command::new("./target/debug/bpfman-ns")
                             .args(prog_args)
                             .status()
@@ -644,7 +898,7 @@ impl BpfManager {
 
                         if !status.success() {
                             return Err(BpfmanError::ContainerAttachError {
-                                program_type: "uprobe".to_string(),
+                                program_type: "usdt".to_string(),
                                 container_pid: program.get_container_pid()?.unwrap(),
                             });
                         }
@@ -663,6 +917,7 @@ impl BpfManager {
                     let map_pin_path = calc_map_pin_path(id);
                     p.get_data_mut().set_map_pin_path(&map_pin_path)?;
                     create_map_pin_path(&map_pin_path)?;
+                    let mut pin_guard = MapPinGuard::new(map_pin_path.clone());
 
                     for (name, map) in loader.maps_mut() {
                         if !should_map_be_pinned(name) {
@@ -675,6 +930,8 @@ impl BpfManager {
                         map.pin(map_pin_path.join(name))
                             .map_err(BpfmanError::UnableToPinMap)?;
                     }
+
+                    pin_guard.persist();
                 }
             }
             Err(_) => {
@@ -688,6 +945,194 @@ impl BpfManager {
         res
     }
 
+    // Tears down a single-attach program's kprobe/uprobe/tracepoint/USDT
+    // attachment (unpin + detach) while leaving the loaded, pinned program
+    // object at `{RTDIR_FS}/prog_{id}` in place, so `reattach_program` can
+    // hook it back up without paying the reload/verify cost again.
+    pub(crate) fn detach_program(&mut self, id: u32) -> Result<(), BpfmanError> {
+        debug!("BpfManager::detach_program() for id: {id}");
+        let program = self.programs.get_mut(&id).ok_or(BpfmanError::Error(format!(
+            "Program {0} does not exist or was not created by bpfman",
+            id,
+        )))?;
+
+        match program {
+            Program::Tracepoint(_) | Program::Kprobe(_) | Program::Uprobe(_) | Program::Usdt(_) => {
+            }
+            _ => {
+                return Err(BpfmanError::Error(
+                    "only tracepoint, kprobe, uprobe, and usdt programs can be detached"
+                        .to_string(),
+                ))
+            }
+        }
+
+        let batch_attachments = program.get_data().get_batch_attachments()?;
+        if batch_attachments.is_empty() {
+            let link_pin_path = format!("{RTDIR_FS}/prog_{id}_link");
+            if Path::new(&link_pin_path).exists() {
+                remove_file(&link_pin_path)
+                    .map_err(|e| BpfmanError::Error(format!("unable to remove link pin: {e}")))?;
+            }
+        } else {
+            for fn_name in &batch_attachments {
+                let link_pin_path = format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}_link");
+                if Path::new(&link_pin_path).exists() {
+                    remove_file(&link_pin_path).map_err(|e| {
+                        BpfmanError::Error(format!("unable to remove link pin: {e}"))
+                    })?;
+                }
+            }
+        }
+        program.get_data_mut().set_attached(false)?;
+
+        info!("Detached program with id: {id}");
+        Ok(())
+    }
+
+    // Re-attaches a program previously torn down by `detach_program` to the
+    // same hook point it was loaded with, reusing the pinned program object
+    // instead of reloading it.
+    pub(crate) fn reattach_program(&mut self, id: u32) -> Result<(), BpfmanError> {
+        debug!("BpfManager::reattach_program() for id: {id}");
+        let program = self.programs.get_mut(&id).ok_or(BpfmanError::Error(format!(
+            "Program {0} does not exist or was not created by bpfman",
+            id,
+        )))?;
+
+        let program_pin_path = format!("{RTDIR_FS}/prog_{id}");
+        let link_pin_path = format!("{RTDIR_FS}/prog_{}_link", id);
+        let batch_attachments = program.get_data().get_batch_attachments()?;
+
+        match program {
+            Program::Tracepoint(ref mut program) => {
+                let mut tracepoint = TracePoint::from_pin(&program_pin_path)
+                    .map_err(BpfmanError::UnableToPinProgram)?;
+                let tracepoint_name = program.get_tracepoint()?;
+                let parts: Vec<&str> = tracepoint_name.split('/').collect();
+                if parts.len() != 2 {
+                    return Err(BpfmanError::InvalidAttach(tracepoint_name.to_string()));
+                }
+                let link_id = tracepoint.attach(parts[0], parts[1])?;
+                let owned_link: TracePointLink = tracepoint.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned tracepoint attach link");
+                fd_link
+                    .pin(link_pin_path)
+                    .map_err(BpfmanError::UnableToPinLink)?;
+            }
+            Program::Kprobe(ref mut program) => {
+                let requested_probe_type = match program.get_retprobe()? {
+                    true => Kretprobe,
+                    false => Kprobe,
+                };
+                if batch_attachments.is_empty() {
+                    let mut kprobe = KProbe::from_pin(&program_pin_path, requested_probe_type)
+                        .map_err(BpfmanError::UnableToPinProgram)?;
+                    let link_id = kprobe.attach(program.get_fn_name()?, program.get_offset()?)?;
+                    let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+                    let fd_link: FdLink = owned_link
+                        .try_into()
+                        .expect("unable to get owned kprobe attach link");
+                    fd_link
+                        .pin(link_pin_path)
+                        .map_err(BpfmanError::UnableToPinLink)?;
+                } else {
+                    for fn_name in &batch_attachments {
+                        let fn_program_pin_path = format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}");
+                        let fn_link_pin_path =
+                            format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}_link");
+                        let mut kprobe =
+                            KProbe::from_pin(&fn_program_pin_path, requested_probe_type)
+                                .map_err(BpfmanError::UnableToPinProgram)?;
+                        let link_id = kprobe.attach(fn_name, program.get_offset()?)?;
+                        let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+                        let fd_link: FdLink = owned_link
+                            .try_into()
+                            .expect("unable to get owned kprobe attach link");
+                        fd_link
+                            .pin(fn_link_pin_path)
+                            .map_err(BpfmanError::UnableToPinLink)?;
+                    }
+                }
+            }
+            Program::Uprobe(ref mut program) => {
+                let requested_probe_type = match program.get_retprobe()? {
+                    true => Uretprobe,
+                    false => Uprobe,
+                };
+                if batch_attachments.is_empty() {
+                    let mut uprobe = UProbe::from_pin(&program_pin_path, requested_probe_type)
+                        .map_err(BpfmanError::UnableToPinProgram)?;
+                    let link_id = uprobe.attach(
+                        program.get_fn_name()?.as_deref(),
+                        program.get_offset()?,
+                        program.get_target()?,
+                        None,
+                    )?;
+                    let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+                    let fd_link: FdLink = owned_link
+                        .try_into()
+                        .expect("unable to get owned uprobe attach link");
+                    fd_link
+                        .pin(link_pin_path)
+                        .map_err(BpfmanError::UnableToPinLink)?;
+                } else {
+                    for fn_name in &batch_attachments {
+                        let fn_program_pin_path = format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}");
+                        let fn_link_pin_path =
+                            format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}_link");
+                        let mut uprobe =
+                            UProbe::from_pin(&fn_program_pin_path, requested_probe_type)
+                                .map_err(BpfmanError::UnableToPinProgram)?;
+                        let link_id = uprobe.attach(
+                            Some(fn_name.as_str()),
+                            program.get_offset()?,
+                            program.get_target()?,
+                            None,
+                        )?;
+                        let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+                        let fd_link: FdLink = owned_link
+                            .try_into()
+                            .expect("unable to get owned uprobe attach link");
+                        fd_link
+                            .pin(fn_link_pin_path)
+                            .map_err(BpfmanError::UnableToPinLink)?;
+                    }
+                }
+            }
+            Program::Usdt(ref mut program) => {
+                let mut usdt =
+                    Usdt::from_pin(&program_pin_path).map_err(BpfmanError::UnableToPinProgram)?;
+                let link_id = usdt.attach(
+                    &program.get_provider()?,
+                    &program.get_probe()?,
+                    program.get_target()?,
+                    None,
+                )?;
+                let owned_link: UsdtLink = usdt.take_link(link_id)?;
+                let fd_link: FdLink = owned_link
+                    .try_into()
+                    .expect("unable to get owned usdt attach link");
+                fd_link
+                    .pin(link_pin_path)
+                    .map_err(BpfmanError::UnableToPinLink)?;
+            }
+            _ => {
+                return Err(BpfmanError::Error(
+                    "only tracepoint, kprobe, uprobe, and usdt programs can be reattached"
+                        .to_string(),
+                ))
+            }
+        }
+
+        program.set_attached();
+
+        info!("Reattached program with id: {id}");
+        Ok(())
+    }
+
     pub(crate) fn remove_program(&mut self, id: u32) -> Result<(), BpfmanError> {
         info!("Removing program with id: {id}");
         let prog = match self.programs.remove(&id) {
@@ -704,17 +1149,23 @@ impl BpfManager {
 
         match prog {
             Program::Xdp(_) | Program::Tc(_) => self.remove_multi_attach_program(&prog)?,
-            Program::Tracepoint(_)
-            | Program::Kprobe(_)
-            | Program::Uprobe(_)
-            | Program::Unsupported(_) => (),
+            Program::Kprobe(_) | Program::Uprobe(_) => cleanup_batch_attachments(id, &prog)?,
+            Program::Tracepoint(_) | Program::Usdt(_) | Program::Unsupported(_) => (),
         }
 
         self.delete_map(id, map_owner_id)?;
+        self.cleanup_prog_array_slots(id)?;
+        self.prog_arrays.retain(|(owner_id, _), _| *owner_id != id);
 
         prog.delete()
             .map_err(BpfmanError::BpfmanProgramDeleteError)?;
 
+        // Unpin the backing image, if any, now that no program references it;
+        // it becomes eligible for LRU eviction again.
+        if let Some(image_content_key) = prog.get_data().get_image_content_key()? {
+            IMAGE_MANAGER.lock().unwrap().unpin_image(&image_content_key);
+        }
+
         Ok(())
     }
 
@@ -903,10 +1354,12 @@ impl BpfManager {
         let mut image_manager = IMAGE_MANAGER.lock().unwrap();
         let pull_result: Result<(String, String), crate::oci_utils::ImageError> = image_manager
             .pull(
+                &*ROOT_DB,
                 &args.image.image_url,
                 args.image.image_pull_policy.clone(),
                 args.image.username.clone(),
                 args.image.password.clone(),
+                args.image.platform.clone(),
                 self.allow_unsigned(),
             );
         let res = match pull_result {
@@ -953,6 +1406,56 @@ impl BpfManager {
                             let _ = args.responder.send(prog);
                         },
                         Command::PullBytecode (args) => self.pull_bytecode(args).unwrap(),
+                        Command::Detach(args) => {
+                            let res = self.detach_program(args.id);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::Reattach(args) => {
+                            let res = self.reattach_program(args.id);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::ListMaps(args) => {
+                            let res = self.list_program_maps(args.id);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::MapLookup(args) => {
+                            let res = self.map_lookup(args.id, &args.map_name, args.key);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::MapUpdate(args) => {
+                            let res = self.map_update(args.id, &args.map_name, args.key, args.value);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::MapDelete(args) => {
+                            let res = self.map_delete(args.id, &args.map_name, args.key);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::MapKeys(args) => {
+                            let res = self.map_keys(args.id, &args.map_name);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::SetProgram(args) => {
+                            let res = self.set_program(args.id, &args.map_name, args.index, args.target_id);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::ClearProgram(args) => {
+                            let res = self.clear_program(args.id, &args.map_name, args.index);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
+                        Command::Follow(args) => {
+                            let res = self.follow_program(args.id);
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        },
                     }
                 }
             }
@@ -960,6 +1463,273 @@ impl BpfManager {
         info!("Stopping processing commands");
     }
 
+    // Returns the map_pin_path a program with this id is sharing its maps
+    // under, without requiring the caller to already own a loaded program
+    // with that id. Lets a client discover what's available for reuse via
+    // map_owner_id before attempting to load a follower program.
+    pub(crate) fn get_map_owner_pin_path(&self, map_owner_id: u32) -> Result<PathBuf, BpfmanError> {
+        if self.maps.contains_key(&map_owner_id) {
+            Ok(calc_map_pin_path(map_owner_id))
+        } else {
+            Err(BpfmanError::Error(
+                "map_owner_id does not exists".to_string(),
+            ))
+        }
+    }
+
+    // Lists the pinned maps belonging to a loaded program, by name, along
+    // with enough of their kernel-reported layout (type, key/value sizes,
+    // max_entries) for a client to decide how to read or update them.
+    pub(crate) fn list_program_maps(&self, id: u32) -> Result<Vec<MapMetadata>, BpfmanError> {
+        let program = self.programs.get(&id).ok_or(BpfmanError::Error(format!(
+            "Program {0} does not exist or was not created by bpfman",
+            id,
+        )))?;
+
+        let map_pin_path = program.get_data().get_map_pin_path()?.ok_or_else(|| {
+            BpfmanError::Error(format!("program {id} does not have any pinned maps"))
+        })?;
+
+        read_pinned_maps(&map_pin_path)
+    }
+
+    // Reads a single entry from one of a loaded program's hash or array
+    // maps, by map name and raw (already-encoded) key bytes.
+    pub(crate) fn map_lookup(
+        &self,
+        id: u32,
+        map_name: &str,
+        key: Vec<u8>,
+    ) -> Result<Vec<u8>, BpfmanError> {
+        let map_pin_path = self.program_map_pin_path(id)?;
+        let map = open_pinned_map(&map_pin_path, map_name)?;
+        bpf_map_lookup_elem(&map, &key)
+    }
+
+    // Writes a single entry into one of a loaded program's hash or array
+    // maps, by map name and raw (already-encoded) key/value bytes. Maps
+    // the kernel reports as frozen (e.g. rodata data sections) are
+    // rejected rather than failing opaquely inside the syscall.
+    pub(crate) fn map_update(
+        &self,
+        id: u32,
+        map_name: &str,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Result<(), BpfmanError> {
+        let map_pin_path = self.program_map_pin_path(id)?;
+        let map = open_pinned_map(&map_pin_path, map_name)?;
+        reject_if_frozen(&map, map_name)?;
+        bpf_map_update_elem(&map, &key, &value)
+    }
+
+    // Deletes a single entry from one of a loaded program's hash or array
+    // maps, by map name and raw (already-encoded) key bytes.
+    pub(crate) fn map_delete(&self, id: u32, map_name: &str, key: Vec<u8>) -> Result<(), BpfmanError> {
+        let map_pin_path = self.program_map_pin_path(id)?;
+        let map = open_pinned_map(&map_pin_path, map_name)?;
+        reject_if_frozen(&map, map_name)?;
+        bpf_map_delete_elem(&map, &key)
+    }
+
+    // Returns every key currently present in one of a loaded program's
+    // hash or array maps, by map name, as raw (still-encoded) key bytes.
+    pub(crate) fn map_keys(&self, id: u32, map_name: &str) -> Result<Vec<Vec<u8>>, BpfmanError> {
+        let map_pin_path = self.program_map_pin_path(id)?;
+        let map = open_pinned_map(&map_pin_path, map_name)?;
+        bpf_map_get_next_key_all(&map)
+    }
+
+    fn program_map_pin_path(&self, id: u32) -> Result<PathBuf, BpfmanError> {
+        let program = self.programs.get(&id).ok_or(BpfmanError::Error(format!(
+            "Program {0} does not exist or was not created by bpfman",
+            id,
+        )))?;
+
+        program.get_data().get_map_pin_path()?.ok_or_else(|| {
+            BpfmanError::Error(format!("program {id} does not have any pinned maps"))
+        })
+    }
+
+    // Tracing programs (tracepoints, kprobes, USDT) conventionally push
+    // records into a perf event array pinned as "EVENTS" rather than being
+    // read back through the one-shot map_* CRUD path, since a follower
+    // wants to keep draining it for as long as the client stays connected.
+    // Spawns one reader task per online CPU, each decoding its buffer's
+    // events and forwarding them into a shared channel; the receiving end
+    // is handed back so the RPC layer can stream it straight to the
+    // client. The task(s) exit on their own once the receiver is dropped,
+    // i.e. once the client stops following.
+    //
+    // Only `BPF_MAP_TYPE_PERF_EVENT_ARRAY` "EVENTS" maps are supported.
+    // Programs whose "EVENTS" map is a `BPF_MAP_TYPE_RINGBUF` instead are
+    // rejected up front with a clear error rather than failing deep inside
+    // `AsyncPerfEventArray::try_from`.
+    pub(crate) fn follow_program(
+        &self,
+        id: u32,
+    ) -> Result<mpsc::Receiver<Result<Vec<u8>, BpfmanError>>, BpfmanError> {
+        if self.programs.get(&id).is_none() {
+            return Err(BpfmanError::ProgramNotLoaded(id));
+        }
+
+        let map_pin_path = self.program_map_pin_path(id)?;
+        let map_data = MapData::from_pin(map_pin_path.join("EVENTS")).map_err(|e| {
+            BpfmanError::Error(format!("unable to open events map for program {id}: {e}"))
+        })?;
+        let map_type = map_data
+            .info()
+            .map_err(|e| BpfmanError::Error(format!("unable to get events map info: {e}")))?
+            .map_type();
+        if !matches!(map_type, aya::maps::MapType::PerfEventArray) {
+            return Err(BpfmanError::Error(format!(
+                "program {id}'s \"EVENTS\" map is a {map_type:?}, but follow only supports \
+                 BPF_MAP_TYPE_PERF_EVENT_ARRAY"
+            )));
+        }
+        let mut perf_array = AsyncPerfEventArray::try_from(map_data).map_err(|e| {
+            BpfmanError::Error(format!(
+                "unable to open perf event array for program {id}: {e}"
+            ))
+        })?;
+
+        let (tx, rx) = mpsc::channel(PERF_BUFFER_CHANNEL_CAPACITY);
+
+        for cpu_id in online_cpus()
+            .map_err(|(_, e)| BpfmanError::Error(format!("unable to determine online cpus: {e}")))?
+        {
+            let mut buf = perf_array.open(cpu_id, None).map_err(|e| {
+                BpfmanError::Error(format!("unable to open perf buffer on cpu {cpu_id}: {e}"))
+            })?;
+            let tx = tx.clone();
+
+            tokio::spawn(async move {
+                let mut buffers = (0..PERF_BUFFER_COUNT)
+                    .map(|_| BytesMut::with_capacity(PERF_BUFFER_PAGE_SIZE))
+                    .collect::<Vec<_>>();
+
+                loop {
+                    let events = match buf.read_events(&mut buffers).await {
+                        Ok(events) => events,
+                        Err(e) => {
+                            let _ = tx
+                                .send(Err(BpfmanError::Error(format!(
+                                    "perf buffer read on cpu {cpu_id} failed: {e}"
+                                ))))
+                                .await;
+                            return;
+                        }
+                    };
+
+                    for buffer in buffers.iter_mut().take(events.read) {
+                        if tx.send(Ok(buffer.to_vec())).await.is_err() {
+                            // The client stopped following; nothing left to do.
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(rx)
+    }
+
+    // Registers `target_id` into a `BPF_MAP_TYPE_PROG_ARRAY` owned by
+    // `owner_id` at `index`, wiring up a tail call from the owner's
+    // program to the target. The target must already be loaded and its
+    // fd still valid, and the index must fall within the array's
+    // max_entries.
+    pub(crate) fn set_program(
+        &mut self,
+        owner_id: u32,
+        map_name: &str,
+        index: u32,
+        target_id: u32,
+    ) -> Result<(), BpfmanError> {
+        if !self.programs.programs.contains_key(&target_id) {
+            return Err(BpfmanError::ProgramNotLoaded(target_id));
+        }
+        // Opening a program's bpffs pin returns a fresh fd referring to
+        // the same underlying kernel program object, just like re-opening
+        // any other pinned bpf object.
+        let target_file = std::fs::File::open(format!("{RTDIR_FS}/prog_{target_id}"))
+            .map_err(|_| BpfmanError::ProgramNotLoaded(target_id))?;
+
+        let map_pin_path = self.program_map_pin_path(owner_id)?;
+        let map = open_pinned_map(&map_pin_path, map_name)?;
+
+        let max_entries = map
+            .info()
+            .map_err(|e| BpfmanError::Error(format!("unable to get map info: {e}")))?
+            .max_entries();
+        if index >= max_entries {
+            return Err(BpfmanError::OutOfBounds { index, max_entries });
+        }
+
+        bpf_map_update_elem(
+            &map,
+            &index.to_ne_bytes(),
+            &(target_file.as_raw_fd() as u32).to_ne_bytes(),
+        )?;
+
+        self.prog_arrays
+            .entry((owner_id, map_name.to_owned()))
+            .or_default()
+            .slots
+            .insert(index, target_id);
+
+        Ok(())
+    }
+
+    // Clears a previously-registered tail-call slot.
+    pub(crate) fn clear_program(
+        &mut self,
+        owner_id: u32,
+        map_name: &str,
+        index: u32,
+    ) -> Result<(), BpfmanError> {
+        let map_pin_path = self.program_map_pin_path(owner_id)?;
+        let map = open_pinned_map(&map_pin_path, map_name)?;
+
+        bpf_map_delete_elem(&map, &index.to_ne_bytes())?;
+
+        if let Some(prog_array) = self.prog_arrays.get_mut(&(owner_id, map_name.to_owned())) {
+            prog_array.slots.remove(&index);
+        }
+
+        Ok(())
+    }
+
+    // Walks every prog-array this program id participates in as a
+    // tail-call target and clears those slots before the program is
+    // deleted, mirroring how `delete_map` decrements `used_by`.
+    fn cleanup_prog_array_slots(&mut self, id: u32) -> Result<(), BpfmanError> {
+        let keys: Vec<(u32, String)> = self
+            .prog_arrays
+            .iter()
+            .filter(|(_, slots)| slots.slots.values().any(|target_id| *target_id == id))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for (owner_id, map_name) in keys {
+            let indices: Vec<u32> = self.prog_arrays[&(owner_id, map_name.clone())]
+                .slots
+                .iter()
+                .filter(|(_, target_id)| **target_id == id)
+                .map(|(index, _)| *index)
+                .collect();
+
+            for index in indices {
+                // The owner program's map directory may already be gone
+                // if the owner itself was removed first; that's fine,
+                // there's nothing left to clear.
+                let _ = self.clear_program(owner_id, &map_name, index);
+            }
+        }
+
+        Ok(())
+    }
+
     // This function checks to see if the user provided map_owner_id is valid.
     fn is_map_owner_id_valid(&mut self, map_owner_id: u32) -> Result<PathBuf, BpfmanError> {
         let map_pin_path = calc_map_pin_path(map_owner_id);
@@ -986,8 +1756,8 @@ impl BpfManager {
         map_owner_id: Option<u32>,
     ) -> Result<(), BpfmanError> {
         if map_owner_id.is_none() {
-            let _ = remove_dir_all(map_pin_path)
-                .map_err(|e| BpfmanError::Error(format!("can't delete map dir: {e}")));
+            // Let MapPinGuard's Drop impl do the removal.
+            MapPinGuard::new(map_pin_path.to_path_buf());
             Ok(())
         } else {
             Ok(())
@@ -1034,7 +1804,14 @@ impl BpfManager {
                 }
             }
             None => {
-                let map = BpfMap { used_by: vec![id] };
+                // Maps adopted from another tool's pin path were not
+                // created by bpfman, so bpfman must never remove_dir_all
+                // that directory on cleanup.
+                let adopted = data.get_external_map_pin_path()?.is_some();
+                let map = BpfMap {
+                    used_by: vec![id],
+                    adopted,
+                };
 
                 self.maps.insert(id, map);
 
@@ -1084,10 +1861,16 @@ impl BpfManager {
 
             if map.used_by.is_empty() {
                 // No more programs using this map, so remove the entry from the map list.
+                // Adopted maps were pinned by another tool, so bpfman only
+                // forgets about them here -- it never owned the directory
+                // and must not delete it.
+                let adopted = map.adopted;
                 let path = calc_map_pin_path(index);
                 self.maps.remove(&index.clone());
-                remove_dir_all(path)
-                    .map_err(|e| BpfmanError::Error(format!("can't delete map dir: {e}")))?;
+                if !adopted {
+                    remove_dir_all(path)
+                        .map_err(|e| BpfmanError::Error(format!("can't delete map dir: {e}")))?;
+                }
             } else {
                 // Update all the programs still using the same map with the updated map_used_by.
                 for id in map.used_by.iter() {
@@ -1134,7 +1917,15 @@ impl BpfManager {
                 }
             }
         } else {
-            let map = BpfMap { used_by: vec![id] };
+            let adopted = program
+                .get_data()
+                .get_external_map_pin_path()
+                .unwrap()
+                .is_some();
+            let map = BpfMap {
+                used_by: vec![id],
+                adopted,
+            };
             self.maps.insert(index, map);
 
             program.get_data_mut().set_maps_used_by(vec![id]).unwrap();
@@ -1154,3 +1945,345 @@ pub fn calc_map_pin_path(id: u32) -> PathBuf {
 pub fn create_map_pin_path(p: &Path) -> Result<(), BpfmanError> {
     create_dir_all(p).map_err(|e| BpfmanError::Error(format!("can't create map dir: {e}")))
 }
+
+// Removes a map_pin_path's directory on drop unless `persist()` is called
+// first. Pinning a program's maps is multi-step (create the directory, then
+// pin each map into it one at a time) and any step can fail partway through;
+// this guard makes sure a mid-loop error can't leave an orphaned directory
+// behind, the way the ad hoc `remove_dir_all` calls that used to be sprinkled
+// across the error paths here could.
+struct MapPinGuard {
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl MapPinGuard {
+    fn new(path: PathBuf) -> Self {
+        MapPinGuard {
+            path,
+            persisted: false,
+        }
+    }
+
+    // Disarms the cleanup once the directory's contents are known good.
+    fn persist(&mut self) {
+        self.persisted = true;
+    }
+}
+
+impl Drop for MapPinGuard {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = remove_dir_all(&self.path)
+                .map_err(|e| debug!("can't delete map dir {}: {e}", self.path.display()));
+        }
+    }
+}
+
+/// Enough of a pinned map's kernel-reported layout for a gRPC/CLI client
+/// to decide how to read or update it, or whether to reuse it via
+/// `map_owner_id` instead of loading its own copy.
+pub struct MapMetadata {
+    pub name: String,
+    pub map_type: String,
+    pub key_size: u32,
+    pub value_size: u32,
+    pub max_entries: u32,
+}
+
+// Opens every file pinned under a program's map directory and reads back
+// its kernel-reported layout. .rodata/.bss/.data maps are never pinned
+// here in the first place (see should_map_be_pinned), so nothing needs to
+// be filtered out.
+fn read_pinned_maps(map_pin_path: &Path) -> Result<Vec<MapMetadata>, BpfmanError> {
+    let mut maps = Vec::new();
+
+    for entry in read_dir(map_pin_path)
+        .map_err(|e| BpfmanError::Error(format!("can't read map dir: {e}")))?
+    {
+        let entry = entry.map_err(|e| BpfmanError::Error(format!("can't read map dir: {e}")))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let data = MapData::from_pin(entry.path())
+            .map_err(|e| BpfmanError::Error(format!("unable to open map {name}: {e}")))?;
+        let info = data
+            .info()
+            .map_err(|e| BpfmanError::Error(format!("unable to get info for map {name}: {e}")))?;
+
+        maps.push(MapMetadata {
+            name,
+            map_type: format!("{:?}", info.map_type()),
+            key_size: info.key_size(),
+            value_size: info.value_size(),
+            max_entries: info.max_entries(),
+        });
+    }
+
+    Ok(maps)
+}
+
+// Owns the fd of a map opened from a bpffs pin, closing it on drop so
+// code that holds one across several operations (lookup-then-update,
+// key iteration) can't leak it on an early return the way a bare
+// `libc::syscall` caller juggling raw fds could.
+struct MapFd {
+    data: MapData,
+}
+
+impl MapFd {
+    fn from_pin(path: &Path, map_name: &str) -> Result<Self, BpfmanError> {
+        let data = MapData::from_pin(path)
+            .map_err(|e| BpfmanError::Error(format!("unable to open map {map_name}: {e}")))?;
+        Ok(MapFd { data })
+    }
+
+    fn as_raw_fd(&self) -> i32 {
+        self.data.fd().as_raw_fd()
+    }
+
+    fn info(&self) -> Result<aya::maps::MapInfo, aya::maps::MapError> {
+        self.data.info()
+    }
+}
+
+fn open_pinned_map(map_pin_path: &Path, map_name: &str) -> Result<MapFd, BpfmanError> {
+    MapFd::from_pin(&map_pin_path.join(map_name), map_name)
+}
+
+// aya's typed map wrappers (HashMap<_, K, V>, Array<_, K, V>) require the
+// key/value types to be known at compile time, but here they're only
+// known at runtime from the pinned map's own metadata. Hash and array
+// maps are looked up/updated directly through the raw bpf(2) syscall
+// instead, the same way tools like bpftool inspect arbitrary maps.
+fn bpf_map_lookup_elem(map: &MapFd, key: &[u8]) -> Result<Vec<u8>, BpfmanError> {
+    let info = map
+        .info()
+        .map_err(|e| BpfmanError::Error(format!("unable to get map info: {e}")))?;
+    if key.len() != info.key_size() as usize {
+        return Err(BpfmanError::Error(format!(
+            "key has length {} but map expects a key of length {}",
+            key.len(),
+            info.key_size()
+        )));
+    }
+    let value_size = info.value_size();
+    let mut value = vec![0u8; value_size as usize];
+
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            BPF_MAP_LOOKUP_ELEM,
+            &bpf_attr_map_elem {
+                map_fd: map.as_raw_fd() as u32,
+                key: key.as_ptr() as u64,
+                value_or_next_key: value.as_mut_ptr() as u64,
+                flags: 0,
+            },
+            std::mem::size_of::<bpf_attr_map_elem>(),
+        )
+    };
+
+    if ret != 0 {
+        return Err(BpfmanError::Error(format!(
+            "map lookup failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    Ok(value)
+}
+
+fn bpf_map_update_elem(map: &MapFd, key: &[u8], value: &[u8]) -> Result<(), BpfmanError> {
+    let info = map
+        .info()
+        .map_err(|e| BpfmanError::Error(format!("unable to get map info: {e}")))?;
+    if key.len() != info.key_size() as usize {
+        return Err(BpfmanError::Error(format!(
+            "key has length {} but map expects a key of length {}",
+            key.len(),
+            info.key_size()
+        )));
+    }
+    if value.len() != info.value_size() as usize {
+        return Err(BpfmanError::Error(format!(
+            "value has length {} but map expects a value of length {}",
+            value.len(),
+            info.value_size()
+        )));
+    }
+
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            BPF_MAP_UPDATE_ELEM,
+            &bpf_attr_map_elem {
+                map_fd: map.as_raw_fd() as u32,
+                key: key.as_ptr() as u64,
+                value_or_next_key: value.as_ptr() as u64,
+                flags: 0,
+            },
+            std::mem::size_of::<bpf_attr_map_elem>(),
+        )
+    };
+
+    if ret != 0 {
+        return Err(BpfmanError::Error(format!(
+            "map update failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    Ok(())
+}
+
+fn bpf_map_delete_elem(map: &MapFd, key: &[u8]) -> Result<(), BpfmanError> {
+    let info = map
+        .info()
+        .map_err(|e| BpfmanError::Error(format!("unable to get map info: {e}")))?;
+    if key.len() != info.key_size() as usize {
+        return Err(BpfmanError::Error(format!(
+            "key has length {} but map expects a key of length {}",
+            key.len(),
+            info.key_size()
+        )));
+    }
+
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            BPF_MAP_DELETE_ELEM,
+            &bpf_attr_map_elem {
+                map_fd: map.as_raw_fd() as u32,
+                key: key.as_ptr() as u64,
+                value_or_next_key: 0,
+                flags: 0,
+            },
+            std::mem::size_of::<bpf_attr_map_elem>(),
+        )
+    };
+
+    if ret != 0 {
+        return Err(BpfmanError::Error(format!(
+            "map delete failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    Ok(())
+}
+
+// Walks every key in a map via BPF_MAP_GET_NEXT_KEY, starting from no key
+// (NULL) and following the kernel's iteration order until ENOENT signals
+// the end.
+fn bpf_map_get_next_key_all(map: &MapFd) -> Result<Vec<Vec<u8>>, BpfmanError> {
+    let key_size = map
+        .info()
+        .map_err(|e| BpfmanError::Error(format!("unable to get map info: {e}")))?
+        .key_size() as usize;
+
+    let mut keys = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+
+    loop {
+        let mut next = vec![0u8; key_size];
+
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_bpf,
+                BPF_MAP_GET_NEXT_KEY,
+                &bpf_attr_map_elem {
+                    map_fd: map.as_raw_fd() as u32,
+                    key: current
+                        .as_ref()
+                        .map(|k| k.as_ptr() as u64)
+                        .unwrap_or(0),
+                    value_or_next_key: next.as_mut_ptr() as u64,
+                    flags: 0,
+                },
+                std::mem::size_of::<bpf_attr_map_elem>(),
+            )
+        };
+
+        if ret != 0 {
+            let err = std::io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ENOENT) {
+                break;
+            }
+            return Err(BpfmanError::Error(format!("map key iteration failed: {err}")));
+        }
+
+        current = Some(next.clone());
+        keys.push(next);
+    }
+
+    Ok(keys)
+}
+
+// BPF_MAP_TYPE_* data-section maps (.rodata etc.) are frozen by the
+// verifier once the owning program loads; reject writes to them up front
+// instead of letting the syscall fail with an opaque EPERM.
+fn reject_if_frozen(map: &MapFd, map_name: &str) -> Result<(), BpfmanError> {
+    let frozen = map
+        .info()
+        .map_err(|e| BpfmanError::Error(format!("unable to get map info: {e}")))?
+        .frozen();
+
+    if frozen {
+        return Err(BpfmanError::Error(format!(
+            "map {map_name} is frozen and cannot be written to"
+        )));
+    }
+
+    Ok(())
+}
+
+const BPF_MAP_LOOKUP_ELEM: i64 = 1;
+const BPF_MAP_UPDATE_ELEM: i64 = 2;
+const BPF_MAP_DELETE_ELEM: i64 = 3;
+const BPF_MAP_GET_NEXT_KEY: i64 = 4;
+
+// Number of per-CPU buffers `follow_program` keeps in flight at once, and
+// the size of each. Matches the defaults aya's own perf array examples use;
+// large enough that a burst of events doesn't get dropped between polls
+// without holding onto more memory than a follower needs.
+const PERF_BUFFER_COUNT: usize = 10;
+const PERF_BUFFER_PAGE_SIZE: usize = 4096;
+const PERF_BUFFER_CHANNEL_CAPACITY: usize = 1024;
+
+#[repr(C)]
+struct bpf_attr_map_elem {
+    map_fd: u32,
+    key: u64,
+    value_or_next_key: u64,
+    flags: u64,
+}
+
+// Matches a loaded ELF function name against a user-supplied batch-attach
+// pattern. A trailing '*' matches any function name sharing the preceding
+// prefix; otherwise the pattern is a comma-separated list of exact names.
+fn matches_probe_pattern(fn_name: &str, pattern: &str) -> bool {
+    if let Some(prefix) = pattern.strip_suffix('*') {
+        fn_name.starts_with(prefix)
+    } else {
+        pattern.split(',').any(|name| name == fn_name)
+    }
+}
+
+// Removes the per-function pin files left behind by a batch-attached
+// kprobe/uprobe program so `remove_program` doesn't leak them.
+fn cleanup_batch_attachments(id: u32, prog: &Program) -> Result<(), BpfmanError> {
+    for fn_name in prog.get_data().get_batch_attachments()? {
+        let link_pin_path = format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}_link");
+        if Path::new(&link_pin_path).exists() {
+            remove_file(&link_pin_path)
+                .map_err(|e| BpfmanError::Error(format!("unable to remove link pin: {e}")))?;
+        }
+
+        let program_pin_path = format!("{RTDIR_FS}/prog_{id}_fn_{fn_name}");
+        if Path::new(&program_pin_path).exists() {
+            remove_file(&program_pin_path)
+                .map_err(|e| BpfmanError::Error(format!("unable to remove program pin: {e}")))?;
+        }
+    }
+
+    Ok(())
+}
@@ -2,24 +2,36 @@
 // Copyright Authors of bpfman
 
 use std::{
-    fs::remove_file,
+    collections::HashSet,
+    fs::{remove_file, File, OpenOptions},
+    io::{Read, Write},
+    os::fd::{AsRawFd, BorrowedFd},
     os::unix::prelude::{FromRawFd, IntoRawFd},
-    path::Path,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
 };
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use bpfman_api::{config::Config, v1::bpfman_server::BpfmanServer};
-use libsystemd::activation::IsType;
-use log::{debug, error, info};
+use libsystemd::{
+    activation::IsType,
+    daemon::{notify, NotifyState},
+};
+use log::{debug, error, info, warn};
+use nix::{
+    fcntl::{flock, FlockArg},
+    sys::socket::{getsockopt, sockopt::PeerCredentials, UnixCredentials},
+};
 use tokio::{
     join,
-    net::UnixListener,
+    net::{UnixListener, UnixStream},
     signal::unix::{signal, SignalKind},
     sync::{broadcast, mpsc},
     task::{JoinHandle, JoinSet},
 };
-use tokio_stream::wrappers::UnixListenerStream;
-use tonic::transport::Server;
+use tokio_stream::{wrappers::UnixListenerStream, Stream};
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
 
 use crate::{
     bpf::BpfManager,
@@ -30,16 +42,56 @@ use crate::{
     utils::{set_file_permissions, SOCK_MODE},
 };
 
+// Guards against two bpfman daemons sharing one state dir (same bpffs /
+// sled DB), which can otherwise silently corrupt pinned state. The
+// returned `File` holds the advisory lock for as long as it's alive, so
+// callers must keep it bound for the life of the process.
+fn acquire_singleton_lock(socket_path: &Path) -> anyhow::Result<File> {
+    let lock_path: PathBuf = socket_path
+        .parent()
+        .unwrap_or_else(|| Path::new("/run/bpfman"))
+        .join("bpfman.lock");
+
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("unable to open lock file {}", lock_path.display()))?;
+
+    match flock(file.as_raw_fd(), FlockArg::LockExclusiveNonblock) {
+        Ok(()) => {
+            file.set_len(0)?;
+            (&file).write_all(std::process::id().to_string().as_bytes())?;
+            Ok(file)
+        }
+        Err(nix::errno::Errno::EWOULDBLOCK) => {
+            let mut holder_pid = String::new();
+            let _ = File::open(&lock_path).and_then(|mut f| f.read_to_string(&mut holder_pid));
+            Err(anyhow!(
+                "another bpfman instance (pid {}) is already running against {}",
+                holder_pid.trim(),
+                lock_path.display()
+            ))
+        }
+        Err(e) => Err(anyhow!("failed to lock {}: {e}", lock_path.display())),
+    }
+}
+
 pub async fn serve(
     config: &Config,
     csi_support: bool,
     timeout: u64,
     socket_path: &Path,
 ) -> anyhow::Result<()> {
+    // Held for the lifetime of this function; dropping it releases the lock.
+    let _singleton_lock = acquire_singleton_lock(socket_path)?;
+
     let (shutdown_tx, shutdown_rx1) = broadcast::channel(32);
     let shutdown_rx2 = shutdown_tx.subscribe();
     let shutdown_rx3 = shutdown_tx.subscribe();
     let shutdown_rx4 = shutdown_tx.subscribe();
+    let shutdown_rx5 = shutdown_tx.subscribe();
+    let shutdown_rx6 = shutdown_tx.subscribe();
     let shutdown_handle = tokio::spawn(shutdown_handler(timeout, shutdown_tx));
 
     let (tx, rx) = mpsc::channel(32);
@@ -63,9 +115,34 @@ pub async fn serve(
     let mut bpf_manager = BpfManager::new(config.clone(), rx, itx);
     bpf_manager.rebuild_state().await?;
 
-    let handle = serve_unix(socket_path, service.clone(), shutdown_rx1).await?;
+    let handle = serve_unix(
+        socket_path,
+        service.clone(),
+        shutdown_rx1,
+        PeerAllowlist::from_config(config),
+    )
+    .await?;
     listeners.push(handle);
 
+    // The Unix socket remains the default transport; a TCP+mTLS listener is
+    // only started when the operator has configured one, so remote/pod-to-
+    // pod management is opt-in.
+    if let Some(tcp) = config.tcp.as_ref() {
+        let handle = serve_tcp(tcp, service.clone(), shutdown_rx5).await?;
+        listeners.push(handle);
+    }
+
+    // State is rebuilt and the listeners are up: tell systemd we're actually
+    // ready rather than just started, so `Type=notify` units don't race
+    // ahead of us. If the unit configured a watchdog interval, keep feeding
+    // it for as long as the daemon's listeners are alive.
+    if let Err(e) = notify(false, &[NotifyState::Ready]) {
+        warn!("failed to notify systemd readiness: {e}");
+    }
+    if let Some(interval) = watchdog_interval() {
+        listeners.push(tokio::spawn(watchdog_task(interval, shutdown_rx6)));
+    }
+
     // TODO(astoycos) see issue #881
     //let static_programs = get_static_programs(static_program_path).await?;
 
@@ -113,6 +190,33 @@ pub async fn serve(
     Ok(())
 }
 
+// `WATCHDOG_USEC` is set by systemd when the unit has `WatchdogSec=` configured;
+// we ping at half that interval, the same margin systemd's own sd_notify(3)
+// documentation recommends.
+fn watchdog_interval() -> Option<std::time::Duration> {
+    std::env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(|usec| std::time::Duration::from_micros(usec) / 2)
+}
+
+async fn watchdog_task(interval: std::time::Duration, mut shutdown_channel: broadcast::Receiver<()>) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if let Err(e) = notify(false, &[NotifyState::Watchdog]) {
+                    warn!("failed to notify systemd watchdog: {e}");
+                }
+            }
+            _ = shutdown_channel.recv() => {
+                debug!("Watchdog: received shutdown signal");
+                break;
+            }
+        }
+    }
+}
+
 pub(crate) async fn shutdown_handler(timeout: u64, shutdown_tx: broadcast::Sender<()>) {
     let mut joinset = JoinSet::new();
     if timeout > 0 {
@@ -135,6 +239,9 @@ pub(crate) async fn shutdown_handler(timeout: u64, shutdown_tx: broadcast::Sende
     });
 
     joinset.join_next().await;
+    if let Err(e) = notify(false, &[NotifyState::Stopping]) {
+        warn!("failed to notify systemd of stopping: {e}");
+    }
     shutdown_tx.send(()).unwrap();
 }
 
@@ -147,16 +254,96 @@ async fn join_listeners(listeners: Vec<JoinHandle<()>>) {
     }
 }
 
+// Allow-list of Unix-socket peer credentials permitted to drive bpfman,
+// read from `Config`. An empty allow-list (the default) preserves today's
+// behavior of trusting anyone who can reach the socket with the right file
+// mode; configuring either list switches to requiring a match.
+#[derive(Clone, Default)]
+struct PeerAllowlist {
+    uids: HashSet<u32>,
+    gids: HashSet<u32>,
+}
+
+impl PeerAllowlist {
+    fn from_config(config: &Config) -> Self {
+        let socket = config.socket.as_ref();
+        Self {
+            uids: socket
+                .map(|s| s.allowed_uids.iter().copied().collect())
+                .unwrap_or_default(),
+            gids: socket
+                .map(|s| s.allowed_gids.iter().copied().collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn is_restricted(&self) -> bool {
+        !self.uids.is_empty() || !self.gids.is_empty()
+    }
+
+    fn permits(&self, uid: u32, gid: u32) -> bool {
+        !self.is_restricted() || self.uids.contains(&uid) || self.gids.contains(&gid)
+    }
+}
+
+fn peer_credentials(stream: &UnixStream) -> std::io::Result<UnixCredentials> {
+    let fd = unsafe { BorrowedFd::borrow_raw(stream.as_raw_fd()) };
+    getsockopt(&fd, PeerCredentials)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+}
+
+// Wraps a `UnixListenerStream`, rejecting connections whose SO_PEERCRED
+// uid/gid aren't in `allow`. Unauthorized peers are dropped (logged) before
+// ever reaching the `BpfmanServer`, rather than relying solely on the
+// socket's file mode.
+struct AuthorizedUnixListenerStream {
+    inner: UnixListenerStream,
+    allow: PeerAllowlist,
+}
+
+impl Stream for AuthorizedUnixListenerStream {
+    type Item = std::io::Result<UnixStream>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(stream))) => match peer_credentials(&stream) {
+                    Ok(cred) if self.allow.permits(cred.uid(), cred.gid()) => {
+                        return Poll::Ready(Some(Ok(stream)));
+                    }
+                    Ok(cred) => {
+                        warn!(
+                            "rejected Unix socket client pid={} uid={} gid={}: not in allow-list",
+                            cred.pid(),
+                            cred.uid(),
+                            cred.gid()
+                        );
+                    }
+                    Err(e) => {
+                        warn!("failed to read peer credentials, rejecting connection: {e}");
+                    }
+                },
+                other => return other,
+            }
+        }
+    }
+}
+
 async fn serve_unix(
     path: &Path,
     service: BpfmanServer<BpfmanLoader>,
     mut shutdown_channel: broadcast::Receiver<()>,
+    allow: PeerAllowlist,
 ) -> anyhow::Result<JoinHandle<()>> {
     let uds_stream = if let Ok(stream) = systemd_unix_stream() {
         stream
     } else {
         std_unix_stream(path).await?
     };
+    let uds_stream = AuthorizedUnixListenerStream {
+        inner: uds_stream,
+        allow,
+    };
 
     let serve = Server::builder()
         .add_service(service)
@@ -180,6 +367,50 @@ async fn serve_unix(
     }))
 }
 
+// Generalizes the gRPC server beyond a local Unix socket: a mutually
+// authenticated TCP listener that runs alongside the Unix socket, sharing
+// the same broadcast shutdown channel, for remote management or agents
+// that talk to bpfman across a pod boundary.
+async fn serve_tcp(
+    tcp: &bpfman_api::config::TcpConfig,
+    service: BpfmanServer<BpfmanLoader>,
+    mut shutdown_channel: broadcast::Receiver<()>,
+) -> anyhow::Result<JoinHandle<()>> {
+    let server_cert = std::fs::read_to_string(&tcp.cert_path)
+        .with_context(|| format!("unable to read TLS cert {}", tcp.cert_path.display()))?;
+    let server_key = std::fs::read_to_string(&tcp.key_path)
+        .with_context(|| format!("unable to read TLS key {}", tcp.key_path.display()))?;
+    let client_ca_cert = std::fs::read_to_string(&tcp.client_ca_cert_path).with_context(|| {
+        format!(
+            "unable to read client CA cert {}",
+            tcp.client_ca_cert_path.display()
+        )
+    })?;
+
+    let tls_config = ServerTlsConfig::new()
+        .identity(Identity::from_pem(server_cert, server_key))
+        .client_ca_root(Certificate::from_pem(client_ca_cert));
+
+    let addr = tcp.addr;
+    let serve = Server::builder()
+        .tls_config(tls_config)?
+        .add_service(service)
+        .serve_with_shutdown(addr, async move {
+            match shutdown_channel.recv().await {
+                Ok(()) => debug!("TCP: Received shutdown signal"),
+                Err(e) => error!("Error receiving shutdown signal {:?}", e),
+            };
+        });
+
+    Ok(tokio::spawn(async move {
+        info!("Listening on {addr} (mTLS)");
+        if let Err(e) = serve.await {
+            eprintln!("Error = {e:?}");
+        }
+        info!("Shutdown TCP Handler {addr}");
+    }))
+}
+
 fn systemd_unix_stream() -> anyhow::Result<UnixListenerStream> {
     let listen_fds = libsystemd::activation::receive_descriptors(true)?;
     if listen_fds.len() == 1 {
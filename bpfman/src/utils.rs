@@ -55,46 +55,92 @@ pub(crate) fn should_map_be_pinned(name: &str) -> bool {
     !(name.contains(".rodata") || name.contains(".bss") || name.contains(".data"))
 }
 
-pub(crate) fn bytes_to_u32(bytes: Vec<u8>) -> u32 {
-    u32::from_ne_bytes(
-        bytes
-            .try_into()
-            .expect("unable to martial &[u8] to &[u8; 4]"),
-    )
+/// Byte order to decode/encode a map value or RPC-transported value with.
+/// Carried alongside the value on the map/program metadata that produced
+/// it, so pinned maps and wire values written on one arch decode correctly
+/// when read on another, instead of assuming the host's native order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Endianness {
+    Little,
+    Big,
+    Native,
 }
 
-pub(crate) fn bytes_to_i32(bytes: Vec<u8>) -> i32 {
-    i32::from_ne_bytes(
-        bytes
-            .try_into()
-            .expect("unable to martial &[u8] to &[u8; 4]"),
-    )
+fn fixed_bytes<const N: usize>(bytes: Vec<u8>) -> Result<[u8; N], BpfmanError> {
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| BpfmanError::InvalidByteLength { expected: N, actual: len })
 }
 
-pub(crate) fn bytes_to_string(bytes: &[u8]) -> String {
-    String::from_utf8(bytes.to_vec()).expect("failed to convert &[u8] to string")
+pub(crate) fn bytes_to_u32(bytes: Vec<u8>, endianness: Endianness) -> Result<u32, BpfmanError> {
+    let bytes = fixed_bytes::<4>(bytes)?;
+    Ok(match endianness {
+        Endianness::Little => u32::from_le_bytes(bytes),
+        Endianness::Big => u32::from_be_bytes(bytes),
+        Endianness::Native => u32::from_ne_bytes(bytes),
+    })
 }
 
-pub(crate) fn bytes_to_bool(bytes: Vec<u8>) -> bool {
-    i8::from_ne_bytes(
-        bytes
-            .try_into()
-            .expect("unable to martial &[u8] to &[i8; 1]"),
-    ) != 0
+pub(crate) fn u32_to_bytes(value: u32, endianness: Endianness) -> Vec<u8> {
+    match endianness {
+        Endianness::Little => value.to_le_bytes().to_vec(),
+        Endianness::Big => value.to_be_bytes().to_vec(),
+        Endianness::Native => value.to_ne_bytes().to_vec(),
+    }
+}
+
+pub(crate) fn bytes_to_i32(bytes: Vec<u8>, endianness: Endianness) -> Result<i32, BpfmanError> {
+    let bytes = fixed_bytes::<4>(bytes)?;
+    Ok(match endianness {
+        Endianness::Little => i32::from_le_bytes(bytes),
+        Endianness::Big => i32::from_be_bytes(bytes),
+        Endianness::Native => i32::from_ne_bytes(bytes),
+    })
+}
+
+pub(crate) fn i32_to_bytes(value: i32, endianness: Endianness) -> Vec<u8> {
+    match endianness {
+        Endianness::Little => value.to_le_bytes().to_vec(),
+        Endianness::Big => value.to_be_bytes().to_vec(),
+        Endianness::Native => value.to_ne_bytes().to_vec(),
+    }
+}
+
+pub(crate) fn bytes_to_u64(bytes: Vec<u8>, endianness: Endianness) -> Result<u64, BpfmanError> {
+    let bytes = fixed_bytes::<8>(bytes)?;
+    Ok(match endianness {
+        Endianness::Little => u64::from_le_bytes(bytes),
+        Endianness::Big => u64::from_be_bytes(bytes),
+        Endianness::Native => u64::from_ne_bytes(bytes),
+    })
+}
+
+pub(crate) fn u64_to_bytes(value: u64, endianness: Endianness) -> Vec<u8> {
+    match endianness {
+        Endianness::Little => value.to_le_bytes().to_vec(),
+        Endianness::Big => value.to_be_bytes().to_vec(),
+        Endianness::Native => value.to_ne_bytes().to_vec(),
+    }
+}
+
+pub(crate) fn bytes_to_usize(bytes: Vec<u8>, endianness: Endianness) -> Result<usize, BpfmanError> {
+    Ok(bytes_to_u64(bytes, endianness)? as usize)
+}
+
+pub(crate) fn usize_to_bytes(value: usize, endianness: Endianness) -> Vec<u8> {
+    u64_to_bytes(value as u64, endianness)
+}
+
+pub(crate) fn bytes_to_bool(bytes: Vec<u8>) -> Result<bool, BpfmanError> {
+    let bytes = fixed_bytes::<1>(bytes)?;
+    Ok(i8::from_ne_bytes(bytes) != 0)
 }
 
-pub(crate) fn bytes_to_usize(bytes: Vec<u8>) -> usize {
-    usize::from_ne_bytes(
-        bytes
-            .try_into()
-            .expect("unable to martial &[u8] to &[u8; 8]"),
-    )
+pub(crate) fn bool_to_bytes(value: bool) -> Vec<u8> {
+    vec![value as u8]
 }
 
-pub(crate) fn bytes_to_u64(bytes: Vec<u8>) -> u64 {
-    u64::from_ne_bytes(
-        bytes
-            .try_into()
-            .expect("unable to martial &[u8] to &[u8; 8]"),
-    )
+pub(crate) fn bytes_to_string(bytes: &[u8]) -> Result<String, BpfmanError> {
+    String::from_utf8(bytes.to_vec()).map_err(|e| BpfmanError::InvalidUtf8(e.to_string()))
 }